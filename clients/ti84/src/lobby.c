@@ -14,6 +14,7 @@ void send_ready(bool ready) {
 
 bool lobby(Packet *packet) {
     bool ready = false;
+    bool disconnected = false;
     gfx_SetDrawBuffer();
 
     while (true) {
@@ -27,13 +28,21 @@ bool lobby(Packet *packet) {
             send_ready(ready);
         }
 
-        if (deserialize_packet(packet) && packet->tag == PACKET_ROUND_LOADING)
-            return true;
-        else
+        if (deserialize_packet(packet)) {
+            if (packet->tag == PACKET_ROUND_LOADING)
+                return true;
+
+            // The transponder lost the server, and sends the `INIT` again once it's back.
+            if (packet->tag == PACKET_DISCONNECTED)
+                disconnected = true;
+            else if (packet->tag == PACKET_CONFIRMED)
+                disconnected = ready = false;
+        } else {
             usb_HandleEvents();
+        }
 
         gfx_FillScreen(0xff);
-        gfx_PrintStringXY("lobby:", 8, 8);
+        gfx_PrintStringXY(disconnected ? "lobby: (reconnecting...)" : "lobby:", 8, 8);
         for (int i = 0; i < LOBBY_LEN; i++) {
             int y = (i + 1) * 12 + (8);
             char *ready = "\0";
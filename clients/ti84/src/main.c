@@ -98,7 +98,7 @@ bool init() {
     os_PutStrFull("initialized serial! :)");
 
     os_SetCursorPos(2, 0);
-    os_PutStrFull("controls: enter to guess, + to submit, clear to quit");
+    os_PutStrFull("controls: enter to guess, + to submit, left/right to look, clear to quit");
 
     os_SetCursorPos(5, 0);
     os_PutStrFull("press any key to continue");
@@ -114,9 +114,19 @@ bool init() {
         usb_HandleEvents();
     };
 
-    PacketData data = {.init = {.options = options}};
+    // The whole screen is used for images, and anything bigger just takes longer to arrive.
+    // Dithering would get rid of banding, but it costs most of what compression saves. Getting
+    // them a pass at a time means that there's something to look at long before that anyway.
+    Display display = {
+        .width = IMAGE_W, .height = IMAGE_H, .depth = DEPTH_RGB332, .dither = DITHER_NONE};
+    PacketData data = {
+        .init = {.capabilities = CAPABILITY_LOOK | CAPABILITY_STANDINGS | CAPABILITY_RECONNECT |
+                                 CAPABILITY_RLE | CAPABILITY_PROGRESSIVE,
+                  .options = options,
+                  .display = display}};
     Packet packet = {.data = data, .tag = PACKET_INIT};
     serialize_packet(&packet);
+    RLE_IMAGES = true;
 
     return true;
 }
@@ -142,8 +152,20 @@ int main(void) {
     }
 
     Packet packet;
-    if (!wait(&packet, PACKET_CONFIRMED))
+    if (!wait(&packet, PACKET_CONFIRMED)) {
+        if (packet.tag == PACKET_ERROR) {
+            os_ClrHome();
+            os_SetCursorPos(0, 0);
+            os_PutStrFull(packet.data.error.reason == REASON_VERSION ? "server version mismatch"
+                                                                     : "rejected by server");
+            while (!os_GetCSC())
+                usb_HandleEvents();
+
+            cleanup();
+        }
+
         return 1;
+    }
 
     gfx_Begin();
     init_palette();
@@ -4,26 +4,77 @@
 #include <stdio.h>
 #include <string.h>
 
+#include "frame.h"
+
 LobbyClient LOBBY[16];
 uint8_t LOBBY_LEN;
 Player PLAYERS[16];
+Standing STANDINGS[16];
+bool RLE_IMAGES;
+Direction SHOWN_DIRECTION;
+
+#if FRAMED
+static uint16_t crc;
+static uint32_t remaining;
+static bool overrun;
+#endif
+
+// Waits until all of `len` bytes have come in.
+static void receive(void *buf, size_t len) {
+    uint8_t *ptr = buf;
+    size_t total = 0;
+
+    while (total < len) {
+        int n = srl_Read(&srl, ptr + total, len - total);
+        if (n > 0) {
+            total += n;
+        } else {
+            usb_HandleEvents();
+        }
+    }
+}
+
+int read_all(void *buf, size_t len) {
+#if FRAMED
+    // Nothing past the end of the frame is read, so a packet that's longer than
+    // its frame can't eat into the next one.
+    if (len > remaining) {
+        overrun = true;
+        len = remaining;
+    }
+
+    remaining -= len;
+    receive(buf, len);
+    crc = crc16(crc, buf, len);
+#else
+    receive(buf, len);
+#endif
+
+    return len;
+}
 
 static uint8_t read_u8(void) {
     uint8_t v;
-    srl_Read(&srl, &v, 1);
+    read_all(&v, 1);
     return v;
 }
 
+static uint16_t read_u16(void) {
+    uint8_t buf[2];
+    read_all(buf, 2);
+    return ((uint16_t)buf[0] << 8) | ((uint16_t)buf[1]);
+}
+
 static uint32_t read_u32(void) {
     uint8_t buf[4];
-    srl_Read(&srl, buf, 4);
+    read_all(buf, 4);
     return ((uint32_t)buf[0] << 24) | ((uint32_t)buf[1] << 16) | ((uint32_t)buf[2] << 8) |
            ((uint32_t)buf[3]);
 }
 
 static float read_f32(void) {
     uint8_t buf[4];
-    srl_Read(&srl, buf, 4);
+    read_all(buf, 4);
     uint32_t tmp = ((uint32_t)buf[0] << 24) | ((uint32_t)buf[1] << 16) | ((uint32_t)buf[2] << 8) |
                    ((uint32_t)buf[3]);
     float f;
@@ -31,25 +82,144 @@ static float read_f32(void) {
     return f;
 }
 
-int read_all(void *buf, size_t len) {
-    uint8_t *ptr = buf;
+// Reads past `len` bytes which aren't needed.
+static void skip(size_t len) {
+    uint8_t pad[16];
+    while (len) {
+        size_t n = len < sizeof pad ? len : sizeof pad;
+        read_all(pad, n);
+        len -= n;
+    }
+}
+
+#if FRAMED
+// Skips ahead to the start of a frame and reads its length, or returns false if there isn't one.
+static bool start_frame(void) {
+    uint8_t byte;
+    if (srl_Read(&srl, &byte, 1) <= 0 || byte != FRAME_SYNC_0)
+        return false;
+
+    while (byte == FRAME_SYNC_0)
+        receive(&byte, 1);
+    if (byte != FRAME_SYNC_1)
+        return false;
+
+    uint8_t len[4];
+    receive(len, 4);
+    remaining = ((uint32_t)len[0] << 24) | ((uint32_t)len[1] << 16) | ((uint32_t)len[2] << 8) |
+                ((uint32_t)len[3]);
+    if (remaining > FRAME_MAX)
+        return false;
+
+    crc = crc16(CRC16_INIT, len, 4);
+    overrun = false;
+    return true;
+}
+
+// Skips whatever's left of the frame, and checks that it wasn't corrupted.
+static bool end_frame(void) {
+    uint8_t pad[16];
+    while (remaining)
+        read_all(pad, remaining < sizeof pad ? remaining : sizeof pad);
+
+    uint8_t trailer[2];
+    receive(trailer, 2);
+    return !overrun && crc == (((uint16_t)trailer[0] << 8) | trailer[1]);
+}
+#endif
+
+// The size of the last image in `ROUND` or `VIEW`, which the passes in `ROWS` are a part of.
+static uint16_t image_width, image_height;
+
+// Which rows of an image are being read, being `first`, `first + step` and so on.
+typedef struct {
+    unsigned char *image;
+    size_t width, first, step;
+} Rows;
+
+// Puts `n` pixels into their rows, from the `at`th one on, either read as they are or all `fill`.
+// They're split up at the end of every row, since the next one in a pass isn't right after it.
+static void put_pixels(const Rows *rows, size_t at, size_t n, int fill) {
+    while (n) {
+        size_t row = at / rows->width, column = at % rows->width;
+        size_t k = rows->width - column < n ? rows->width - column : n;
+        unsigned char *dst = rows->image + (rows->first + row * rows->step) * rows->width + column;
+
+        if (fill < 0)
+            read_all(dst, k);
+        else
+            memset(dst, fill, k);
+        at += k;
+        n -= k;
+    }
+}
+
+// Reads `count` rows that are `len` bytes on the wire into their places in the image, decoding
+// them as they come in if they're run-length encoded, since there isn't room to keep a compressed
+// copy around. Only one byte pixels are supported, which is all that `DEPTH_RGB332` needs.
+static bool read_rows(const Rows *rows, size_t count, size_t len, size_t *image_len) {
+    const size_t max = rows->width * count;
+
+    if (!RLE_IMAGES) {
+        if (len > max)
+            return false;
+        put_pixels(rows, 0, len, -1);
+        *image_len = len;
+        return true;
+    }
+
     size_t total = 0;
+    while (len) {
+        uint8_t control = read_u8();
+        len--;
 
-    while (total < len) {
-        int n = srl_Read(&srl, ptr + total, len - total);
-        if (n > 0) {
+        if (control < 0x80) {
+            size_t n = control + 1;
+            if (n > len || total + n > max)
+                return false;
+            put_pixels(rows, total, n, -1);
+            len -= n;
             total += n;
         } else {
-            usb_HandleEvents();
+            size_t n = control - 0x7E;
+            if (len < 1 || total + n > max)
+                return false;
+            put_pixels(rows, total, n, read_u8());
+            len--;
+            total += n;
         }
     }
 
-    return total;
+    *image_len = total;
+    return true;
+}
+
+// Reads a whole image into `image`, which has to fit on the screen. For progressive images, this
+// is empty, and the rows come in later with `read_pass`.
+static bool read_image(unsigned char *image, uint16_t width, uint16_t height, size_t len,
+                       size_t *image_len) {
+    if (width > IMAGE_W || height > IMAGE_H)
+        return false;
+
+    image_width = width;
+    image_height = height;
+    Rows rows = {.image = image, .width = width, .first = 0, .step = 1};
+    return read_rows(&rows, height, len, image_len);
+}
+
+// Reads one pass of the last image into `image`, where the whole image would've gone.
+static bool read_pass(unsigned char *image, uint16_t first, uint16_t step, size_t len,
+                      size_t *image_len) {
+    if (step == 0 || first >= image_height)
+        return false;
+
+    Rows rows = {.image = image, .width = image_width, .first = first, .step = step};
+    return read_rows(&rows, (image_height - first + step - 1) / step, len, image_len);
 }
 
 static void deserialize_client_options(ClientOptions *opt) {
     opt->color = (Color)read_u8();
-    srl_Read(&srl, opt->user, 16);
+    read_all(opt->user, 16);
 }
 
 static void deserialize_coordinate(Coordinate *c) {
@@ -66,7 +236,7 @@ static void deserialize_player(Player *p) {
         deserialize_coordinate(&p->guess);
     else {
         uint8_t pad[8];
-        srl_Read(&srl, pad, 8);
+        read_all(pad, 8);
     }
 }
 
@@ -91,22 +261,48 @@ static void deserialize_result(RoundData *r) {
         deserialize_player(&r->players[i]);
 }
 
+static void deserialize_game(GameData *g) {
+    size_t len = read_u32();
+    g->standings_len = len < 16 ? len : 16;
+    g->standings = STANDINGS;
+    for (size_t i = 0; i < len; i++) {
+        // Anyone past the first 16 is read and dropped, since there's no room left for them.
+        Standing standing;
+        standing.id = read_u32();
+        standing.points = read_u32();
+        standing.distance = read_f32();
+        if (i < g->standings_len)
+            g->standings[i] = standing;
+    }
+
+    g->has_best = read_u8();
+    g->best.id = read_u32();
+    g->best.round = read_u32();
+    g->best.distance = read_f32();
+}
+
 bool deserialize_packet(Packet *p) {
     uint8_t tag;
+#if FRAMED
+    if (!start_frame())
+        return false;
+    tag = read_u8();
+#else
     int n = srl_Read(&srl, &tag, 1);
     if (n <= 0) {
         return false;
     }
+#endif
 
+    // Everything after the tag waits for its bytes to come in.
     p->tag = tag;
-    // Stall for a bit to wait for all the data to come in.
-    // TODO: Fix this shitty hack.
-    for (volatile int i = 0; i < 50000; i++)
-        usb_HandleEvents();
 
     switch (p->tag) {
     case PACKET_CONFIRMED:
+        p->data.confirmed.version = read_u16();
+        p->data.confirmed.capabilities = read_u32();
         p->data.confirmed.id = read_u32();
+        read_all(p->data.confirmed.token, 8);
         deserialize_client_options(&p->data.confirmed.options);
         deserialize_clients(&p->data.confirmed.lobby);
         break;
@@ -120,19 +316,60 @@ bool deserialize_packet(Packet *p) {
         break;
     case PACKET_ROUND:
         p->data.round.number = read_u32();
-        p->data.round.image_len = read_u32();
+        p->data.round.deadline = read_u32();
+        p->data.round.width = read_u16();
+        p->data.round.height = read_u16();
         p->data.round.image = (unsigned char *)gfx_vbuffer;
-        read_all(p->data.round.image, p->data.round.image_len - 1);
+        if (!read_image(p->data.round.image, p->data.round.width, p->data.round.height, read_u32(),
+                        &p->data.round.image_len))
+            return false;
+        break;
+    case PACKET_VIEW:
+        p->data.view.direction = (Direction)read_u8();
+        p->data.view.width = read_u16();
+        p->data.view.height = read_u16();
+        p->data.view.image = (unsigned char *)gfx_vbuffer;
+        if (!read_image(p->data.view.image, p->data.view.width, p->data.view.height, read_u32(),
+                        &p->data.view.image_len))
+            return false;
         break;
+    case PACKET_ROWS: {
+        p->data.rows.direction = (Direction)read_u8();
+        p->data.rows.first = read_u16();
+        p->data.rows.step = read_u16();
+        p->data.rows.image = (unsigned char *)gfx_vbuffer;
+        size_t len = read_u32();
+        if (p->data.rows.direction != SHOWN_DIRECTION) {
+            skip(len);
+            p->data.rows.image_len = 0;
+            break;
+        }
+
+        if (!read_pass(p->data.rows.image, p->data.rows.first, p->data.rows.step, len,
+                       &p->data.rows.image_len))
+            return false;
+        break;
+    }
     case PACKET_GUESSED:
         p->data.guessed.player = read_u32();
         break;
     case PACKET_RESULT:
         deserialize_result(&p->data.results.round);
         break;
+    case PACKET_GAME_OVER:
+        deserialize_game(&p->data.game_over.game);
+        break;
+    case PACKET_ERROR:
+        p->data.error.reason = (Reason)read_u8();
+        p->data.error.version = read_u16();
+        break;
     default:
         break;
     }
 
+#if FRAMED
+    return end_frame();
+#else
     return true;
+#endif
 }
\ No newline at end of file
@@ -4,4 +4,12 @@
 extern LobbyClient LOBBY[16];
 extern uint8_t LOBBY_LEN;
 extern Player PLAYERS[16];
+extern Standing STANDINGS[16];
+
+// Whether images come run-length encoded, which is what asking for `CAPABILITY_RLE` gets.
+extern bool RLE_IMAGES;
+
+// Which way the image on the screen looks. `ROWS` for any other direction are read past instead
+// of drawn, since they're what's left of an image that was looked away from.
+extern Direction SHOWN_DIRECTION;
 bool deserialize_packet(Packet *p);
@@ -0,0 +1,18 @@
+#pragma once
+#include <stddef.h>
+#include <stdint.h>
+
+#include "shared.h"
+
+// Every frame starts with these, see `shared/src/serial.rs` for the whole layout.
+#define FRAME_SYNC_0 0xA5
+#define FRAME_SYNC_1 0x5A
+
+// Same as the longest packet the transponder will send with its default limits, with room for
+// a run-length encoded image that didn't compress at all.
+#define FRAME_MAX (IMAGE_W * IMAGE_H + IMAGE_W * IMAGE_H / 128 + 64 * 32 + 64)
+
+#define CRC16_INIT 0xFFFF
+
+// Continues a CRC-16/CCITT-FALSE, starting from `CRC16_INIT`.
+uint16_t crc16(uint16_t crc, const void *data, size_t len);
@@ -3,18 +3,54 @@
 #include <stdint.h>
 #include <string.h>
 
-static void write_u8(uint8_t v) { srl_Write(&srl, &v, 1); }
+#include "frame.h"
+
+#if FRAMED
+// Packets sent by the client are tiny, so each one is put together here first
+// to know how long its frame will be.
+static uint8_t frame[64];
+static size_t frame_len;
+#endif
+
+static void put(const void *buf, size_t len) {
+#if FRAMED
+    memcpy(frame + frame_len, buf, len);
+    frame_len += len;
+#else
+    srl_Write(&srl, buf, len);
+#endif
+}
+
+static void write_u8(uint8_t v) { put(&v, 1); }
+
+static void write_u16(uint16_t v) {
+    uint8_t buf[2] = {(v >> 8) & 0xFF, v & 0xFF};
+    put(buf, 2);
+}
+
+static void write_u32(uint32_t v) {
+    uint8_t buf[4] = {(v >> 24) & 0xFF, (v >> 16) & 0xFF, (v >> 8) & 0xFF, v & 0xFF};
+    put(buf, 4);
+}
 
 static void write_f32(float f) {
     uint32_t tmp;
     memcpy(&tmp, &f, 4);
     uint8_t buf[4] = {(tmp >> 24) & 0xFF, (tmp >> 16) & 0xFF, (tmp >> 8) & 0xFF, tmp & 0xFF};
-    srl_Write(&srl, buf, 4);
+    put(buf, 4);
 }
 
 static void serialize_client_options(const ClientOptions *opt) {
     write_u8((uint8_t)opt->color);
-    srl_Write(&srl, opt->user, 16);
+    put(opt->user, 16);
+}
+
+static void serialize_display(const Display *d) {
+    write_u16(d->width);
+    write_u16(d->height);
+    write_u8((uint8_t)d->depth);
+    write_u8((uint8_t)d->dither);
+    write_u32(d->max_payload);
 }
 
 static void serialize_coordinate(const Coordinate *c) {
@@ -23,10 +59,17 @@ static void serialize_coordinate(const Coordinate *c) {
 }
 
 void serialize_packet(const Packet *p) {
+#if FRAMED
+    frame_len = 0;
+#endif
+
     write_u8((uint8_t)p->tag);
     switch (p->tag) {
     case PACKET_INIT:
+        write_u16(PROTOCOL_VERSION);
+        write_u32(p->data.init.capabilities);
         serialize_client_options(&p->data.init.options);
+        serialize_display(&p->data.init.display);
         break;
     case PACKET_WAITING_STATUS:
         write_u8(p->data.waiting_status.ready);
@@ -34,7 +77,24 @@ void serialize_packet(const Packet *p) {
     case PACKET_GUESS:
         serialize_coordinate(&p->data.guess.coordinates);
         break;
+    case PACKET_LOOK:
+        write_u8((uint8_t)p->data.look.direction);
+        break;
+    case PACKET_RESUME:
+        put(p->data.resume.token, 8);
+        break;
     default:
         break;
     }
-}
\ No newline at end of file
+
+#if FRAMED
+    uint8_t header[6] = {FRAME_SYNC_0, FRAME_SYNC_1, 0, 0, 0, frame_len};
+    uint16_t crc = crc16(CRC16_INIT, header + 2, 4);
+    crc = crc16(crc, frame, frame_len);
+    uint8_t trailer[2] = {crc >> 8, crc & 0xFF};
+
+    srl_Write(&srl, header, sizeof header);
+    srl_Write(&srl, frame, frame_len);
+    srl_Write(&srl, trailer, sizeof trailer);
+#endif
+}
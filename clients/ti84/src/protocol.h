@@ -0,0 +1,50 @@
+// Generated from `protocol.json` by `cargo run -p codegen`, so don't edit this by hand.
+#ifndef PROTOCOL_H
+#define PROTOCOL_H
+
+#define PROTOCOL_VERSION 4
+
+// How many characters long room codes are.
+#define ROOM_CODE_LEN 4
+
+#define CAPABILITY_LOOK (1 << 0)
+#define CAPABILITY_STANDINGS (1 << 1)
+#define CAPABILITY_ROOMS (1 << 2)
+#define CAPABILITY_RESUME (1 << 3)
+#define CAPABILITY_RECONNECT (1 << 4)
+#define CAPABILITY_RLE (1 << 5)
+#define CAPABILITY_PROGRESSIVE (1 << 6)
+
+typedef enum { LOBBY_JOIN = 0, LOBBY_RETURN, LOBBY_LEAVE, LOBBY_READY } LobbyAction;
+typedef enum { RED = 0, GREEN, BLUE, CYAN, MAGENTA, YELLOW } Color;
+typedef enum { DIRECTION_LEFT = 0, DIRECTION_CENTER, DIRECTION_RIGHT } Direction;
+typedef enum { REASON_VERSION = 0, REASON_UNKNOWN, REASON_ILLEGAL, REASON_MALFORMED } Reason;
+typedef enum { DEPTH_RGB332 = 0, DEPTH_RGB888, DEPTH_INDEXED } Depth;
+typedef enum { DITHER_NONE = 0, DITHER_FLOYD_STEINBERG, DITHER_ORDERED } Dither;
+typedef enum {
+    PACKET_INIT = 1,
+    PACKET_CONFIRMED,
+    PACKET_LOBBY_EVENT,
+    PACKET_WAITING_STATUS,
+    PACKET_ROUND_LOADING,
+    PACKET_ROUND,
+    PACKET_GUESS,
+    PACKET_GUESSED,
+    PACKET_RESULT,
+    PACKET_REQUEST_GAME_END,
+    PACKET_SOFT_QUIT,
+    PACKET_LOOK,
+    PACKET_VIEW,
+    PACKET_GAME_OVER,
+    PACKET_CREATE_ROOM,
+    PACKET_JOIN_ROOM,
+    PACKET_ROOM,
+    PACKET_ROOM_UNAVAILABLE,
+    PACKET_RESUME,
+    PACKET_RESUME_FAILED,
+    PACKET_ERROR,
+    PACKET_DISCONNECTED,
+    PACKET_ROWS
+} PacketTag;
+
+#endif // PROTOCOL_H
@@ -2,6 +2,7 @@
 #include <shared.h>
 #include <srldrvce.h>
 #include <stdbool.h>
+#include <stdio.h>
 #include <ti/getcsc.h>
 
 #include "deserialize.h"
@@ -28,6 +29,47 @@ void draw_coord(Coordinate coord, Color color, bool cross) {
     }
 }
 
+void look(Direction direction) {
+    PacketData data = {.look = {.direction = direction}};
+    Packet packet = {.data = data, .tag = PACKET_LOOK};
+    serialize_packet(&packet);
+}
+
+static const char *player_name(size_t id) {
+    for (int i = 0; i < LOBBY_LEN; i++) {
+        if (LOBBY[i].id == id)
+            return LOBBY[i].options.user;
+    }
+
+    return "?";
+}
+
+static void show_standings(GameData *game) {
+    gfx_FillScreen(0xff);
+    gfx_PrintStringXY("final standings:", 8, 8);
+
+    char string[40];
+    for (size_t i = 0; i < game->standings_len; i++) {
+        Standing standing = game->standings[i];
+        sprintf(string, "%u. %.16s - %lu", (unsigned int)(i + 1), player_name(standing.id),
+                (unsigned long)standing.points);
+        gfx_PrintStringXY(string, 8, (i + 1) * 12 + 8);
+    }
+
+    if (game->has_best) {
+        sprintf(string, "best: %.16s, %u km", player_name(game->best.id),
+                (unsigned int)game->best.distance);
+        gfx_PrintStringXY(string, 8, (game->standings_len + 2) * 12 + 8);
+    }
+
+    while (!os_GetCSC()) {
+        usb_HandleEvents();
+    }
+
+    gfx_FillScreen(0xff);
+    gfx_PrintStringXY("waiting for others...", 8, 8);
+}
+
 bool do_round(State *state) {
     Packet packet;
 
@@ -39,6 +81,7 @@ bool do_round(State *state) {
     render_map();
 
     gfx_SetDrawScreen();
+    SHOWN_DIRECTION = DIRECTION_CENTER;
     if (!wait(&packet, PACKET_ROUND))
         return false;
     gfx_SwapDraw();
@@ -50,6 +93,8 @@ bool do_round(State *state) {
     const unsigned short CURSOR_SPEED = 4;
     unsigned short cursor_x = ORIGIN_X, cursor_y = ORIGIN_Y;
     bool guesser = false;
+    bool over = false;
+    Direction direction = DIRECTION_CENTER;
 
     while (true) {
         uint8_t key = os_GetCSC();
@@ -58,6 +103,21 @@ bool do_round(State *state) {
         }
 
         usb_HandleEvents();
+
+        // The rest of a progressive image goes straight onto the screen as it comes in, which
+        // only has the image on it while the map isn't up. The round can also run out meanwhile.
+        if (!guesser) {
+            gfx_SetDrawScreen();
+            bool received = deserialize_packet(&packet);
+            gfx_SetDrawBuffer();
+
+            if (received && packet.tag == PACKET_ERROR)
+                return false;
+            if (received && packet.tag == PACKET_RESULT) {
+                over = true;
+                break;
+            }
+        }
         if (key == sk_Add) {
             guess(cursor_x, cursor_y);
             break;
@@ -69,6 +129,32 @@ bool do_round(State *state) {
             guesser = !guesser;
         }
 
+        if (!guesser && (key == sk_Left || key == sk_Right)) {
+            Direction target = direction;
+            if (key == sk_Left && direction != DIRECTION_LEFT)
+                target = direction - 1;
+            if (key == sk_Right && direction != DIRECTION_RIGHT)
+                target = direction + 1;
+
+            if (target != direction) {
+                // Passes of the old image can still be on their way, and are dropped from here on.
+                direction = target;
+                SHOWN_DIRECTION = direction;
+                look(direction);
+
+                gfx_SetDrawScreen();
+                if (!wait_either(&packet, PACKET_VIEW, PACKET_RESULT))
+                    return false;
+                gfx_SetDrawBuffer();
+
+                // The round can run out before the server gets to the `LOOK`.
+                if (packet.tag == PACKET_RESULT) {
+                    over = true;
+                    break;
+                }
+            }
+        }
+
         if (guesser && key != 0) {
             gfx_SetDrawScreen();
             clear_cursor(cursor_x, cursor_y);
@@ -92,11 +178,13 @@ bool do_round(State *state) {
         }
     }
 
-    gfx_SetDrawScreen();
-    gfx_FillScreen(0xff);
-    gfx_PrintStringXY("waiting for other guesses...", 8, 8);
-    if (!wait(&packet, PACKET_RESULT))
-        return false;
+    if (!over) {
+        gfx_SetDrawScreen();
+        gfx_FillScreen(0xff);
+        gfx_PrintStringXY("waiting for other guesses...", 8, 8);
+        if (!wait(&packet, PACKET_RESULT))
+            return false;
+    }
 
     gfx_SetDrawBuffer();
     render_map();
@@ -149,6 +237,9 @@ bool do_round(State *state) {
                 *state = STATE_ROUND;
                 return true;
                 break;
+            case PACKET_GAME_OVER:
+                show_standings(&packet.data.game_over.game);
+                break;
             case PACKET_LOBBY_EVENT:
                 if (packet.data.lobby_event.action != LOBBY_RETURN)
                     break;
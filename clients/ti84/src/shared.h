@@ -5,27 +5,20 @@
 #include <stddef.h>
 #include <stdint.h>
 
+#include "protocol.h"
+
 #define PORT 4000
 #define BAUD 9600 * 4
 #define IMAGE_W 320
 #define IMAGE_H 240
 
-typedef enum { STATE_ROUND, STATE_LOBBY } State;
+// Whether packets are wrapped in checksummed frames on the serial link, which the
+// transponder then has to be started with `--framed` for. Build with `make FRAMED=1`.
+#ifndef FRAMED
+#define FRAMED 0
+#endif
 
-typedef enum { LOBBY_JOIN = 0, LOBBY_RETURN, LOBBY_LEAVE, LOBBY_READY } LobbyAction;
-typedef enum { RED = 0, GREEN, BLUE, CYAN, MAGENTA, YELLOW } Color;
-typedef enum {
-    PACKET_INIT = 1,
-    PACKET_CONFIRMED,
-    PACKET_LOBBY_EVENT,
-    PACKET_WAITING_STATUS,
-    PACKET_ROUND_LOADING,
-    PACKET_ROUND,
-    PACKET_GUESS,
-    PACKET_GUESSED,
-    PACKET_RESULT,
-    PACKET_REQUEST_GAME_END
-} PacketTag;
+typedef enum { STATE_ROUND, STATE_LOBBY } State;
 
 typedef struct {
     float longitude;
@@ -37,6 +30,14 @@ typedef struct {
     char user[16];
 } ClientOptions;
 
+typedef struct {
+    uint16_t width;
+    uint16_t height;
+    Depth depth;
+    Dither dither;
+    uint32_t max_payload;
+} Display;
+
 typedef struct {
     size_t id;
     bool ready;
@@ -63,12 +64,36 @@ typedef struct {
     Player *players;
 } RoundData;
 
+typedef struct {
+    size_t id;
+    uint32_t points;
+    float distance;
+} Standing;
+
+typedef struct {
+    size_t id;
+    size_t round;
+    float distance;
+} BestGuess;
+
+typedef struct {
+    size_t standings_len;
+    Standing *standings;
+    bool has_best;
+    BestGuess best;
+} GameData;
+
 typedef union {
     struct {
+        uint32_t capabilities;
         ClientOptions options;
+        Display display;
     } init;
     struct {
+        uint16_t version;
+        uint32_t capabilities;
         size_t id;
+        uint8_t token[8];
         ClientOptions options;
         LobbyClients lobby;
     } confirmed;
@@ -85,6 +110,8 @@ typedef union {
     } round_loading;
     struct {
         size_t number;
+        uint32_t deadline;
+        uint16_t width, height;
         size_t image_len;
         unsigned char *image;
     } round;
@@ -99,6 +126,31 @@ typedef union {
     } results;
     struct {
     } return_to_lobby;
+    struct {
+        Direction direction;
+    } look;
+    struct {
+        Direction direction;
+        uint16_t width, height;
+        size_t image_len;
+        unsigned char *image;
+    } view;
+    struct {
+        GameData game;
+    } game_over;
+    struct {
+        uint8_t token[8];
+    } resume;
+    struct {
+        Reason reason;
+        uint16_t version;
+    } error;
+    struct {
+        Direction direction;
+        uint16_t first, step;
+        size_t image_len;
+        unsigned char *image;
+    } rows;
 } PacketData;
 
 typedef struct {
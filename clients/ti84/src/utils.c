@@ -12,7 +12,10 @@ inline void cleanup() {
     usb_Cleanup();
 }
 
-bool wait(Packet *packet, PacketTag target) {
+bool wait(Packet *packet, PacketTag target) { return wait_either(packet, target, target); }
+
+// Waits for whichever of two packets comes first, dropping anything else in the meantime.
+bool wait_either(Packet *packet, PacketTag first, PacketTag second) {
     while (has_srl_device) {
         if (os_GetCSC() == sk_Clear) {
             cleanup();
@@ -24,8 +27,12 @@ bool wait(Packet *packet, PacketTag target) {
             continue;
         }
 
-        if (packet->tag == target)
+        if (packet->tag == first || packet->tag == second)
             return true;
+
+        // The server is about to drop us, so there's no point in waiting any longer.
+        if (packet->tag == PACKET_ERROR)
+            return false;
     }
 
     return false;
@@ -3,4 +3,5 @@
 #include <stdbool.h>
 
 void cleanup();
-bool wait(Packet *packet, PacketTag target);
\ No newline at end of file
+bool wait(Packet *packet, PacketTag target);
+bool wait_either(Packet *packet, PacketTag first, PacketTag second);
\ No newline at end of file
@@ -0,0 +1,259 @@
+// Checks the packet (de)serializers against the golden fixtures from the shared Rust crate,
+// built for a regular computer with the stubs in `stubs/`. Run it with `./run.sh`.
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "deserialize.h"
+#include "device.h"
+#include "serialize.h"
+#include "shared.h"
+
+bool has_srl_device = true;
+srl_device_t srl;
+uint8_t gfx_vbuffer[240][320];
+
+static const char *directory;
+static const char *current;
+static int failures = 0;
+
+static uint8_t input[1 << 16];
+static size_t input_len, input_pos;
+static uint8_t output[1 << 16];
+static size_t output_len;
+
+int srl_Read(srl_device_t *srl, void *buf, size_t len) {
+    (void)srl;
+    if (len > 0 && input_pos >= input_len) {
+        // Reading past the end would make `read_all` spin forever.
+        fprintf(stderr, "%s: read past the end of the fixture\n", current);
+        exit(1);
+    }
+
+    size_t n = input_len - input_pos < len ? input_len - input_pos : len;
+    memcpy(buf, input + input_pos, n);
+    input_pos += n;
+    return n;
+}
+
+int srl_Write(srl_device_t *srl, const void *buf, size_t len) {
+    (void)srl;
+    memcpy(output + output_len, buf, len);
+    output_len += len;
+    return len;
+}
+
+usb_error_t usb_HandleEvents(void) { return 0; }
+
+#define CHECK(cond)                                                                                \
+    do {                                                                                           \
+        if (!(cond)) {                                                                             \
+            fprintf(stderr, "%s: check failed: %s\n", current, #cond);                            \
+            failures++;                                                                            \
+        }                                                                                          \
+    } while (0)
+
+static void load(const char *name) {
+    char path[512];
+    snprintf(path, sizeof(path), "%s/%s.%s", directory, name, FRAMED ? "frame" : "bin");
+
+    FILE *file = fopen(path, "rb");
+    if (!file) {
+        fprintf(stderr, "couldn't open %s\n", path);
+        exit(1);
+    }
+
+    current = name;
+    input_len = fread(input, 1, sizeof(input), file);
+    input_pos = 0;
+    output_len = 0;
+    fclose(file);
+}
+
+static void deserialize(const char *name, Packet *p, PacketTag tag) {
+    load(name);
+    CHECK(deserialize_packet(p));
+    CHECK(p->tag == tag);
+    CHECK(input_pos == input_len);
+}
+
+static void serialize(const char *name, const Packet *p) {
+    load(name);
+    serialize_packet(p);
+    CHECK(output_len == input_len);
+    CHECK(memcmp(output, input, input_len) == 0);
+}
+
+static void check_clients(const LobbyClients *lobby) {
+    CHECK(lobby->len == 2);
+    CHECK(lobby->clients[0].id == 3);
+    CHECK(lobby->clients[0].ready);
+    CHECK(lobby->clients[0].options.color == CYAN);
+    CHECK(strncmp(lobby->clients[0].options.user, "bobby", 16) == 0);
+    CHECK(lobby->clients[1].id == 7);
+    CHECK(!lobby->clients[1].ready);
+    CHECK(lobby->clients[1].options.color == YELLOW);
+    CHECK(memcmp(lobby->clients[1].options.user, "sixteen chars!!!", 16) == 0);
+}
+
+static const uint8_t TOKEN[8] = {0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef};
+
+static void check_deserialize(void) {
+    Packet p;
+
+    deserialize("confirmed", &p, PACKET_CONFIRMED);
+    CHECK(p.data.confirmed.version == PROTOCOL_VERSION);
+    CHECK(p.data.confirmed.capabilities == 0x7F);
+    CHECK(p.data.confirmed.id == 3);
+    CHECK(memcmp(p.data.confirmed.token, TOKEN, 8) == 0);
+    CHECK(p.data.confirmed.options.color == CYAN);
+    check_clients(&p.data.confirmed.lobby);
+
+    deserialize("lobby_event", &p, PACKET_LOBBY_EVENT);
+    CHECK(p.data.lobby_event.action == LOBBY_LEAVE);
+    CHECK(p.data.lobby_event.user == 7);
+    check_clients(&p.data.lobby_event.lobby);
+
+    deserialize("round_loading", &p, PACKET_ROUND_LOADING);
+    check_clients(&p.data.round_loading.lobby);
+
+    deserialize("round", &p, PACKET_ROUND);
+    CHECK(p.data.round.number == 2);
+    CHECK(p.data.round.deadline == 30);
+    CHECK(p.data.round.width == 32 && p.data.round.height == 16);
+    CHECK(p.data.round.image_len == 512);
+    CHECK(p.data.round.image[0] == 0 && p.data.round.image[511] == 255);
+
+    RLE_IMAGES = true;
+    deserialize("round_rle", &p, PACKET_ROUND);
+    CHECK(p.data.round.width == 67 && p.data.round.height == 2);
+    CHECK(p.data.round.image_len == 134);
+    CHECK(p.data.round.image[0] == 7 && p.data.round.image[1] == 7);
+    CHECK(p.data.round.image[2] == 1 && p.data.round.image[4] == 3);
+    CHECK(p.data.round.image[5] == 9 && p.data.round.image[133] == 9);
+    RLE_IMAGES = false;
+
+    deserialize("guessed", &p, PACKET_GUESSED);
+    CHECK(p.data.guessed.player == 7);
+
+    deserialize("result", &p, PACKET_RESULT);
+    RoundData *round = &p.data.results.round;
+    CHECK(round->number == 2);
+    CHECK(round->answer.latitude == 48.5f && round->answer.longitude == 19.25f);
+    CHECK(round->players_len == 2);
+    CHECK(round->players[0].id == 3 && round->players[0].has_guess);
+    CHECK(round->players[0].guess.latitude == -33.75f);
+    CHECK(round->players[0].guess.longitude == 151.125f);
+    CHECK(round->players[0].points == 1500 && round->players[0].delta == 250);
+    CHECK(round->players[1].id == 7 && !round->players[1].has_guess);
+    CHECK(round->players[1].points == 900 && round->players[1].delta == 0);
+
+    deserialize("view", &p, PACKET_VIEW);
+    CHECK(p.data.view.direction == DIRECTION_LEFT);
+    CHECK(p.data.view.width == 16 && p.data.view.height == 32);
+    CHECK(p.data.view.image_len == 512);
+    CHECK(p.data.view.image[0] == 0 && p.data.view.image[511] == 255);
+
+    deserialize("game_over", &p, PACKET_GAME_OVER);
+    GameData *game = &p.data.game_over.game;
+    CHECK(game->standings_len == 2);
+    CHECK(game->standings[0].id == 3 && game->standings[0].points == 1500);
+    CHECK(game->standings[1].distance == 4321.25f);
+    CHECK(game->has_best);
+    CHECK(game->best.id == 3 && game->best.round == 1 && game->best.distance == 12.75f);
+
+    deserialize("game_over_no_best", &p, PACKET_GAME_OVER);
+    CHECK(p.data.game_over.game.standings_len == 0);
+    CHECK(!p.data.game_over.game.has_best);
+
+    // Only the first 16 standings fit, but the rest still have to be read past.
+    deserialize("game_over_crowded", &p, PACKET_GAME_OVER);
+    game = &p.data.game_over.game;
+    CHECK(game->standings_len == 16);
+    CHECK(game->standings[15].id == 15 && game->standings[15].points == 1500);
+    CHECK(game->has_best);
+    CHECK(game->best.id == 19 && game->best.round == 4 && game->best.distance == 0.5f);
+
+    deserialize("error", &p, PACKET_ERROR);
+    CHECK(p.data.error.reason == REASON_VERSION);
+    CHECK(p.data.error.version == PROTOCOL_VERSION);
+
+    deserialize("disconnected", &p, PACKET_DISCONNECTED);
+
+    // The rows go wherever they are in the last image, which is the 16x32 one from `view`.
+    deserialize("view", &p, PACKET_VIEW);
+    SHOWN_DIRECTION = DIRECTION_LEFT;
+    deserialize("rows", &p, PACKET_ROWS);
+    CHECK(p.data.rows.direction == DIRECTION_LEFT);
+    CHECK(p.data.rows.first == 4 && p.data.rows.step == 8);
+    CHECK(p.data.rows.image_len == 64);
+    CHECK(p.data.rows.image[4 * 16] == 0 && p.data.rows.image[4 * 16 + 15] == 15);
+    CHECK(p.data.rows.image[12 * 16] == 16 && p.data.rows.image[28 * 16 + 15] == 63);
+    CHECK(p.data.rows.image[5 * 16] == 80);
+
+    // After looking somewhere else, the rest of the old image is read past without drawing it.
+    SHOWN_DIRECTION = DIRECTION_RIGHT;
+    memset(gfx_vbuffer, 0xaa, sizeof(gfx_vbuffer));
+    deserialize("rows", &p, PACKET_ROWS);
+    CHECK(p.data.rows.image_len == 0);
+    CHECK(p.data.rows.image[4 * 16] == 0xaa && p.data.rows.image[28 * 16 + 15] == 0xaa);
+}
+
+static void check_serialize(void) {
+    ClientOptions options = {.color = CYAN};
+    memset(options.user, 0, 16);
+    memcpy(options.user, "bobby", 5);
+
+    Packet init = {
+        .tag = PACKET_INIT,
+        .data = {.init = {.capabilities = CAPABILITY_LOOK | CAPABILITY_STANDINGS,
+                          .options = options,
+                          .display = {.width = 160,
+                                      .height = 120,
+                                      .depth = DEPTH_INDEXED,
+                                      .dither = DITHER_ORDERED,
+                                      .max_payload = 0x12345}}},
+    };
+    serialize("init", &init);
+
+    Packet waiting = {.tag = PACKET_WAITING_STATUS, .data = {.waiting_status = {.ready = true}}};
+    serialize("waiting_status", &waiting);
+
+    Packet guess = {.tag = PACKET_GUESS,
+                    .data = {.guess = {.coordinates = {.latitude = 48.5f, .longitude = 19.25f}}}};
+    serialize("guess", &guess);
+
+    Packet look = {.tag = PACKET_LOOK, .data = {.look = {.direction = DIRECTION_RIGHT}}};
+    serialize("look", &look);
+
+    Packet resume = {.tag = PACKET_RESUME};
+    memcpy(resume.data.resume.token, TOKEN, 8);
+    serialize("resume", &resume);
+
+    Packet end = {.tag = PACKET_REQUEST_GAME_END};
+    serialize("request_game_end", &end);
+
+    Packet quit = {.tag = PACKET_SOFT_QUIT};
+    serialize("soft_quit", &quit);
+}
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s <fixtures>\n", argv[0]);
+        return 1;
+    }
+
+    directory = argv[1];
+    check_deserialize();
+    check_serialize();
+
+    if (failures) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+
+    printf("all %s fixtures match\n", FRAMED ? "framed" : "raw");
+    return 0;
+}
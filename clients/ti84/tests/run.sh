@@ -0,0 +1,16 @@
+#!/bin/sh
+# Generates the golden fixtures from the shared Rust crate, and checks the
+# C packet code against them, compiled for this computer instead of the calculator,
+# both with and without serial framing.
+set -e
+
+cd "$(dirname "$0")"
+out="${TMPDIR:-/tmp}/geoterm-fixtures"
+
+GEOTERM_FIXTURES="$out" cargo test -q -p shared --test golden fixtures
+for framed in 0 1; do
+    cc -Wall -Wextra -std=c11 -Istubs -I../src -I../src/packets -DFRAMED=$framed \
+        fixtures.c ../src/packets/deserialize.c ../src/packets/serialize.c ../src/packets/frame.c \
+        -o "$out/check"
+    "$out/check" "$out"
+done
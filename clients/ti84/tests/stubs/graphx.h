@@ -0,0 +1,5 @@
+// Just enough of the CE toolchain's graphics library to build the packet code on a regular computer.
+#pragma once
+#include <stdint.h>
+
+extern uint8_t gfx_vbuffer[240][320];
@@ -0,0 +1,10 @@
+// Just enough of the CE toolchain's serial driver to build the packet code on a regular computer.
+#pragma once
+#include <stddef.h>
+
+typedef struct {
+    int unused;
+} srl_device_t;
+
+int srl_Read(srl_device_t *srl, void *buf, size_t len);
+int srl_Write(srl_device_t *srl, const void *buf, size_t len);
@@ -0,0 +1,8 @@
+// Just enough of the CE toolchain's USB driver to build the packet code on a regular computer.
+#pragma once
+
+typedef int usb_error_t;
+typedef int usb_event_t;
+typedef void usb_callback_data_t;
+
+usb_error_t usb_HandleEvents(void);
@@ -1,16 +1,20 @@
-use bytes::BytesMut;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
 use crossterm::event::KeyCode;
+use image::RgbImage;
 use shared::{
-    Packet, PacketWriteExt,
-    image::{HEIGHT, WIDTH, decode},
+    Direction, Packet, PacketWriteExt,
+    image::{decode, decompress, len},
 };
 
 use crate::{
-    Client, Message, State,
+    Client, DISPLAY, Message, State,
     ui::{
         lobby::{self, Lobby},
         results::{self, Results},
         round::{self, Round},
+        standings::{self, Standings},
     },
 };
 
@@ -21,6 +25,17 @@ pub enum Result {
     Continue,
 }
 
+/// Decodes a run-length encoded image from the server, or [`None`] if it's broken, in which case
+/// there's just nothing to look at.
+fn view(width: u16, height: u16, image: Bytes) -> Option<RgbImage> {
+    let (width, height) = (width as u32, height as u32);
+    let decoded = len(width, height, DISPLAY.depth)
+        .and_then(|size| decompress(&image, DISPLAY.depth, size))
+        .and_then(|pixels| decode(&pixels, width, height, DISPLAY.depth));
+
+    decoded.ok()
+}
+
 pub(crate) trait Handler {
     fn handle(
         &mut self,
@@ -66,22 +81,44 @@ impl Handler for Loading {
     async fn handle(
         &mut self,
         message: crate::Message,
-        _client: &mut crate::Client,
+        client: &mut crate::Client,
     ) -> eyre::Result<Result> {
         Ok(match message {
-            Message::Packet(packet) => match packet {
-                Packet::Round { number, image } => {
-                    Result::ChangeState(State::Round(round::Round {
-                        image_len: image.len(),
-                        image: decode(BytesMut::from(image), WIDTH, HEIGHT)?,
-                        cursor: (0.0, 0.0),
-                        guessed: false,
-                        guessing: false,
-                        number,
-                    }))
-                }
-                _ => Result::Unhandled,
-            },
+            Message::Packet(Packet::Round {
+                number,
+                deadline,
+                width,
+                height,
+                image,
+            }) => {
+                let image_len = image.len();
+                let mut views = [None, None, None];
+                views[Direction::Center as usize] = view(width, height, image);
+
+                Result::ChangeState(State::Round(round::Round {
+                    image_len,
+                    direction: Direction::Center,
+                    deadline: (deadline != 0)
+                        .then(|| Instant::now() + Duration::from_secs(deadline as u64)),
+                    views,
+                    cursor: (0.0, 0.0),
+                    guessed: false,
+                    guessing: false,
+                    number,
+                }))
+            }
+            // The server couldn't get the round going after all.
+            Message::Packet(Packet::LobbyEvent {
+                action: shared::lobby::Action::Return,
+                lobby,
+                ..
+            }) => Result::ChangeState(State::Lobby(lobby::Lobby {
+                clients: lobby,
+                username: client.options.user.clone(),
+                ready: false,
+                id: client.id,
+                room: client.room.clone(),
+            })),
             _ => Result::Unhandled,
         })
     }
@@ -109,6 +146,28 @@ impl Handler for Round {
                     self.guessing = !self.guessing;
                     Result::Continue
                 }
+                KeyCode::Char('a') | KeyCode::Char('d') => {
+                    let target = if key == KeyCode::Char('a') {
+                        self.direction.left()
+                    } else {
+                        self.direction.right()
+                    };
+
+                    let Some(direction) = target else {
+                        return Ok(Result::Continue);
+                    };
+
+                    if self.views[direction as usize].is_some() {
+                        self.direction = direction;
+                    } else {
+                        client
+                            .writer
+                            .write_packet(Packet::Look { direction })
+                            .await?;
+                    }
+
+                    Result::Continue
+                }
                 KeyCode::Up | KeyCode::Char('i') => {
                     self.cursor.1 += 3.0;
                     Result::Continue
@@ -139,12 +198,23 @@ impl Handler for Round {
                             username: client.options.user.clone(),
                             ready: false,
                             id: client.id,
+                            room: client.room.clone(),
                         }))
                     } else {
                         Result::Unhandled
                     }
                 }
                 Packet::Guessed { player: _ } => Result::Continue,
+                Packet::View {
+                    direction,
+                    width,
+                    height,
+                    image,
+                } => {
+                    self.views[direction as usize] = view(width, height, image);
+                    self.direction = direction;
+                    Result::Continue
+                }
                 Packet::Result { results } => {
                     Result::ChangeState(State::Results(results::Results {
                         ready: false,
@@ -180,6 +250,14 @@ impl Handler for Results {
                     client.lobby = lobby;
                     Result::ChangeState(State::Loading)
                 }
+                Packet::GameOver { results } => {
+                    Result::ChangeState(State::Standings(standings::Standings {
+                        id: client.id,
+                        data: results,
+                        players: self.lobby.clone(),
+                        lobby: self.lobby.clone(),
+                    }))
+                }
                 Packet::LobbyEvent {
                     action,
                     user: _,
@@ -191,6 +269,7 @@ impl Handler for Results {
                             username: client.options.user.clone(),
                             ready: false,
                             id: client.id,
+                            room: client.room.clone(),
                         }))
                     } else {
                         self.lobby = lobby;
@@ -203,3 +282,29 @@ impl Handler for Results {
         })
     }
 }
+
+impl Handler for Standings {
+    async fn handle(&mut self, message: Message, client: &mut Client) -> eyre::Result<Result> {
+        Ok(match message {
+            Message::Key(KeyCode::Char('l')) => Result::ChangeState(State::Lobby(lobby::Lobby {
+                clients: self.lobby.clone(),
+                username: client.options.user.clone(),
+                ready: false,
+                id: client.id,
+                room: client.room.clone(),
+            })),
+            Message::Packet(packet) => match packet {
+                Packet::RoundLoading { lobby } => {
+                    client.lobby = lobby;
+                    Result::ChangeState(State::Loading)
+                }
+                Packet::LobbyEvent { lobby, .. } => {
+                    self.lobby = lobby;
+                    Result::Continue
+                }
+                _ => Result::Unhandled,
+            },
+            _ => Result::Unhandled,
+        })
+    }
+}
@@ -1,7 +1,9 @@
 use crossterm::event::KeyCode;
 use shared::{
-    BufferedSplitExt, ClientOptions, LOCALHOST, Packet, PacketReadExt, PacketWriteExt, Reader,
-    Writer, lobby::Clients,
+    BufferedSplitExt, Capabilities, ClientOptions, DEFAULT_ROOM, Depth, Display, Dither, LOCALHOST,
+    PROTOCOL_VERSION, Packet, PacketReadExt, PacketWriteExt, Reader, Reason, Writer,
+    image::{HEIGHT, WIDTH},
+    lobby::Clients,
 };
 use tokio::{
     io::AsyncWriteExt,
@@ -12,7 +14,7 @@ use tokio::{
 
 use crate::{
     logic::{Handler, Loading},
-    ui::{UI, lobby, results, round},
+    ui::{UI, lobby, results, round, standings},
 };
 
 pub mod logic;
@@ -23,12 +25,29 @@ pub enum Message {
     Quit,
     Ready,
     Resize,
+    Tick,
     Key(KeyCode),
     Packet(Packet),
 }
 
+/// A palette picked for each image looks as good as full color in a terminal, at a third of the size.
+pub const DISPLAY: Display = Display {
+    width: WIDTH as u16,
+    height: HEIGHT as u16,
+    depth: Depth::Indexed,
+    dither: Dither::FloydSteinberg,
+    max_payload: 0,
+};
+
+fn rejected(reason: Reason, version: u16) -> eyre::Report {
+    eyre::eyre!(
+        "rejected by server: {reason:?} (server protocol version {version}, ours {PROTOCOL_VERSION})"
+    )
+}
+
 struct Client {
     id: usize,
+    room: String,
     options: ClientOptions,
     writer: Writer,
     rx: Receiver<Message>,
@@ -47,24 +66,57 @@ impl Client {
         Ok(())
     }
 
-    pub async fn new(options: ClientOptions) -> eyre::Result<Self> {
+    /// Joins a room before initializing, creating a new one if the code is `new`.
+    async fn join(reader: &mut Reader, writer: &mut Writer, code: &str) -> eyre::Result<String> {
+        let packet = if code == "new" {
+            Packet::CreateRoom
+        } else {
+            Packet::JoinRoom {
+                code: code.to_uppercase(),
+            }
+        };
+        writer.write_packet(packet).await?;
+
+        match reader.read_packet().await? {
+            Packet::Room { code, .. } => Ok(code),
+            Packet::RoomUnavailable { code } => Err(eyre::eyre!("room {code} is unavailable")),
+            Packet::Error { reason, version } => Err(rejected(reason, version)),
+            other => Err(shared::Error::Illegal(other).into()),
+        }
+    }
+
+    pub async fn new(options: ClientOptions, room: Option<String>) -> eyre::Result<Self> {
         let (tx, rx) = mpsc::channel(8);
         let stream = TcpStream::connect(LOCALHOST).await?;
         let (mut reader, mut writer) = stream.buffered_split();
 
+        let room = match room {
+            Some(code) => Self::join(&mut reader, &mut writer, &code).await?,
+            None => DEFAULT_ROOM.to_owned(),
+        };
+
         writer
             .write_packet(Packet::Init {
+                capabilities: Capabilities::LOOK
+                    | Capabilities::STANDINGS
+                    | Capabilities::ROOMS
+                    | Capabilities::RLE,
                 options: options.clone(),
+                display: DISPLAY,
             })
             .await?;
 
         let (id, .., lobby) = match reader.read_packet().await? {
-            Packet::Confirmed { id, options, lobby } => (id, options, lobby),
+            Packet::Confirmed {
+                id, options, lobby, ..
+            } => (id, options, lobby),
+            Packet::Error { reason, version } => return Err(rejected(reason, version)),
             other => return Err(shared::Error::Illegal(other).into()),
         };
 
         let client = Self {
             id,
+            room,
             options,
             writer,
             rx,
@@ -88,6 +140,7 @@ pub enum State {
     Lobby(lobby::Lobby),
     Round(round::Round),
     Results(results::Results),
+    Standings(standings::Standings),
 }
 
 #[tokio::main]
@@ -98,13 +151,15 @@ async fn main() -> eyre::Result<()> {
         user: String::from("bobby"),
     };
 
-    let mut client = Client::new(options).await?;
+    // The first argument is an optional room code to join, or `new` to create a room.
+    let mut client = Client::new(options, std::env::args().nth(1)).await?;
     let mut terminal = ratatui::init();
     let mut state = State::Lobby(lobby::Lobby {
         id: client.id,
         ready: false,
         clients: client.lobby.clone(),
         username: "bobby".to_string(),
+        room: client.room.clone(),
     });
 
     let mut ui = UI::init(client.tx.clone());
@@ -116,7 +171,7 @@ async fn main() -> eyre::Result<()> {
                 break 'main;
             }
 
-            if message == Message::Resize {
+            if message == Message::Resize || message == Message::Tick {
                 ui.render(&mut terminal, &state)?;
                 continue;
             }
@@ -126,6 +181,7 @@ async fn main() -> eyre::Result<()> {
                 State::Loading => Loading::handle(&mut Loading, message, &mut client).await,
                 State::Round(round) => round.handle(message, &mut client).await,
                 State::Results(results) => results.handle(message, &mut client).await,
+                State::Standings(standings) => standings.handle(message, &mut client).await,
             }?;
 
             match result {
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use futures::StreamExt;
 use ratatui::{
@@ -13,6 +15,7 @@ pub mod loading;
 pub mod lobby;
 pub mod results;
 pub mod round;
+pub mod standings;
 
 pub fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
     let [area] = Layout::horizontal([horizontal])
@@ -24,6 +27,7 @@ pub fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect
 
 pub struct UI {
     input: JoinHandle<eyre::Result<()>>,
+    ticker: JoinHandle<eyre::Result<()>>,
     _tx: Sender<crate::Message>,
 }
 
@@ -43,6 +47,7 @@ impl UI {
             }
             State::Loading => loading::render(area, buf, "loading..."),
             State::Results(results) => results.render(area, buf),
+            State::Standings(standings) => standings.render(area, buf),
         }
     }
 
@@ -71,9 +76,19 @@ impl UI {
         Ok(())
     }
 
+    /// Periodically rerenders, so that things like the round countdown stay up to date.
+    async fn ticker(tx: Sender<crate::Message>) -> eyre::Result<()> {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            tx.send(Message::Tick).await?;
+        }
+    }
+
     pub fn init(tx: Sender<crate::Message>) -> Self {
         Self {
             _tx: tx.clone(),
+            ticker: tokio::spawn(Self::ticker(tx.clone())),
             input: tokio::spawn(Self::input(tx)),
         }
     }
@@ -82,6 +97,7 @@ impl UI {
 impl Drop for UI {
     fn drop(&mut self) {
         self.input.abort();
+        self.ticker.abort();
         ratatui::restore();
     }
 }
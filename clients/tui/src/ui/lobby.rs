@@ -15,6 +15,7 @@ pub struct Lobby {
     pub username: String,
     pub ready: bool,
     pub id: usize,
+    pub room: String,
 }
 
 impl Widget for &Lobby {
@@ -53,7 +54,7 @@ impl Widget for &Lobby {
 
         let block = Block::bordered()
             .title_alignment(Alignment::Center)
-            .title(" lobby ".to_string());
+            .title(format!(" lobby {} ", self.room));
         block.render(centered, buf);
 
         Paragraph::new(format!("user: {}", self.username)).render(layout[0], buf);
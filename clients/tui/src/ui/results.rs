@@ -54,7 +54,9 @@ impl Widget for &Results {
                         .find(|x| x.id == player.id)
                         .unwrap()
                         .options;
-                    let guess = player.guess.unwrap();
+                    let Some(guess) = player.guess else {
+                        continue;
+                    };
 
                     ctx.draw(&Points {
                         coords: &[(guess.longitude as f64, guess.latitude as f64)],
@@ -9,9 +9,17 @@ use ratatui::{
         canvas::{self, Canvas, Context, Map},
     },
 };
+use shared::{
+    Direction,
+    image::{HEIGHT, WIDTH},
+};
+use std::time::Instant;
 
 pub struct Round {
-    pub image: RgbImage,
+    /// The three slices of the panorama, which are only present once they've been received.
+    pub views: [Option<RgbImage>; 3],
+    pub direction: Direction,
+    pub deadline: Option<Instant>,
     pub image_len: usize,
     pub number: usize,
     pub guessed: bool,
@@ -20,6 +28,11 @@ pub struct Round {
 }
 
 impl Round {
+    /// The current view, unless the server sent something that couldn't be decoded.
+    pub fn image(&self) -> Option<&RgbImage> {
+        self.views[self.direction as usize].as_ref()
+    }
+
     fn draw_guesser(&self, ctx: &mut Context<'_>) {
         ctx.draw(&Map {
             resolution: canvas::MapResolution::High,
@@ -33,7 +46,12 @@ impl Round {
     }
 
     fn draw_image(&self, ctx: &mut Context<'_>, height: f64) {
-        let points = self.image.enumerate_pixels().map(|(x, y, p)| {
+        let Some(image) = self.image() else {
+            ctx.print(0.0, height / 2.0, "this view couldn't be shown".dark_gray());
+            return;
+        };
+
+        let points = image.enumerate_pixels().map(|(x, y, p)| {
             let [r, g, b] = p.0;
             let color = Color::Rgb(r, g, b);
             let y = height - y as f64;
@@ -51,9 +69,7 @@ impl Round {
 
 impl Widget for &Round {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let image = &self.image;
-
-        let (width, height) = image.dimensions();
+        let (width, height) = self.image().map_or((WIDTH, HEIGHT), RgbImage::dimensions);
         let (width, height) = (width as f64, height as f64);
         Canvas::default()
             .marker(symbols::Marker::HalfBlock)
@@ -77,9 +93,22 @@ impl Widget for &Round {
             .block(
                 Block::bordered()
                     .padding(Padding::new(1, 0, 1, 0))
-                    .title(format!(" Round {} ", self.number))
+                    .title(match self.deadline {
+                        Some(deadline) => format!(
+                            " Round {} ({}s left) ",
+                            self.number,
+                            deadline.saturating_duration_since(Instant::now()).as_secs()
+                        ),
+                        None => format!(" Round {} ", self.number),
+                    })
                     .title_alignment(Alignment::Center)
-                    .title_bottom(format!(" {}uess, {}ubmit ", "[g]".bold(), "[s]".bold())),
+                    .title_bottom(format!(
+                        " {}uess, {}ubmit, {}/{} look ",
+                        "[g]".bold(),
+                        "[s]".bold(),
+                        "[a]".bold(),
+                        "[d]".bold()
+                    )),
             )
             .render(area, buf);
     }
@@ -0,0 +1,79 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Padding, Paragraph, Widget},
+};
+use shared::{GameResult, lobby::Clients};
+
+use crate::ui::center;
+
+pub struct Standings {
+    pub id: usize,
+    pub data: GameResult,
+
+    /// The lobby as it was during the game, used for looking up names.
+    pub players: Clients,
+
+    /// The lobby which will be returned to.
+    pub lobby: Clients,
+}
+
+impl Standings {
+    fn name(&self, id: usize) -> Span<'_> {
+        if id == self.id {
+            return Span::styled("you", Style::new().italic());
+        }
+
+        let name = self
+            .players
+            .into_iter()
+            .find(|x| x.id == id)
+            .map(|x| x.options.user.as_str())
+            .unwrap_or("?");
+
+        Span::raw(name)
+    }
+}
+
+impl Widget for &Standings {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let len = self.data.standings.len() as u16;
+        let centered = center(area, Constraint::Length(48), Constraint::Length(6 + len));
+
+        let mut text: Vec<Line> = self
+            .data
+            .standings
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let mut line = Line::raw(format!("{}. ", i + 1));
+                line.push_span(self.name(x.id));
+                line.push_span(format!(" - {} ({:.0} km)", x.points, x.distance));
+                line
+            })
+            .collect();
+
+        text.push(Line::default());
+        if let Some(best) = self.data.best {
+            let mut line = Line::raw("best guess: ");
+            line.push_span(self.name(best.id));
+            line.push_span(format!(
+                ", {:.0} km off in round {}",
+                best.distance, best.round
+            ));
+            text.push(line);
+        }
+
+        Paragraph::new(text)
+            .block(
+                Block::bordered()
+                    .title(" Final Standings ")
+                    .padding(Padding::new(1, 1, 1, 0))
+                    .title_alignment(Alignment::Center)
+                    .title_bottom(format!(" {}obby ", "[l]".bold())),
+            )
+            .render(centered, buf);
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt::Write as _;
+
+use crate::schema::{Schema, screaming};
+
+/// Same as the TI84 client's `.clang-format`.
+const COLUMNS: usize = 100;
+
+/// A `typedef enum`, on one line if it fits.
+fn typedef(out: &mut String, name: &str, variants: &[(String, u32)]) {
+    let mut items = Vec::new();
+    let mut next = None;
+    for (variant, value) in variants {
+        // Values are only written out when they don't follow the last one.
+        if next == Some(*value) {
+            items.push(variant.clone());
+        } else {
+            items.push(format!("{variant} = {value}"));
+        }
+        next = Some(value + 1);
+    }
+
+    let line = format!("typedef enum {{ {} }} {name};", items.join(", "));
+    if line.len() <= COLUMNS {
+        writeln!(out, "{line}").unwrap();
+    } else {
+        writeln!(
+            out,
+            "typedef enum {{\n    {}\n}} {name};",
+            items.join(",\n    ")
+        )
+        .unwrap();
+    }
+}
+
+/// Generates `clients/ti84/src/protocol.h`, which is everything the client's hand written
+/// packet code needs from the schema.
+pub fn generate(schema: &Schema) -> String {
+    let mut out = String::from(
+        "// Generated from `protocol.json` by `cargo run -p codegen`, so don't edit this by hand.\n\
+         #ifndef PROTOCOL_H\n\
+         #define PROTOCOL_H\n\n",
+    );
+
+    writeln!(out, "#define PROTOCOL_VERSION {}\n", schema.version).unwrap();
+    for constant in &schema.constants {
+        writeln!(out, "// {}", constant.doc).unwrap();
+        writeln!(out, "#define {} {}\n", constant.name, constant.value).unwrap();
+    }
+
+    for capability in &schema.capabilities {
+        writeln!(
+            out,
+            "#define CAPABILITY_{} (1 << {})",
+            capability.name, capability.bit
+        )
+        .unwrap();
+    }
+    writeln!(out).unwrap();
+
+    for enumeration in &schema.enums {
+        let variants: Vec<(String, u32)> = enumeration
+            .variants
+            .iter()
+            .zip(0..)
+            .map(|(x, i)| (format!("{}{}", enumeration.prefix, screaming(&x.name)), i))
+            .collect();
+        typedef(&mut out, &enumeration.name, &variants);
+    }
+
+    let tags: Vec<(String, u32)> = schema
+        .sent()
+        .map(|x| (format!("PACKET_{}", x.name), x.tag as u32))
+        .collect();
+    typedef(&mut out, "PacketTag", &tags);
+
+    writeln!(out, "\n#endif // PROTOCOL_H").unwrap();
+    out
+}
@@ -0,0 +1,67 @@
+//! Generates the Rust codec, the TI84 client's C header and the tables in `PROTOCOL.md`
+//! from the schema in `protocol.json`, so that new packets only have to be described once.
+//!
+//! The TI84 client's packet structs and (de)serializers aren't generated, just its constants,
+//! tags and enums, so a new packet still has to be written out in C by hand.
+
+use std::{fs, path::Path};
+
+use clap::Parser;
+use eyre::bail;
+
+use crate::schema::Schema;
+
+mod c;
+mod markdown;
+mod rust;
+mod schema;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Fails if any of the generated files are out of date, instead of writing them.
+    #[arg(short, long)]
+    check: bool,
+}
+
+fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let schema = Schema::parse(&fs::read_to_string(root.join("protocol.json"))?)?;
+
+    let protocol = root.join("PROTOCOL.md");
+    let outputs = [
+        (root.join("shared/src/codec.rs"), rust::generate(&schema)?),
+        (
+            root.join("clients/ti84/src/protocol.h"),
+            c::generate(&schema),
+        ),
+        (
+            protocol.clone(),
+            markdown::generate(&schema, &fs::read_to_string(&protocol)?)?,
+        ),
+    ];
+
+    let mut stale = Vec::new();
+    for (path, contents) in outputs {
+        if fs::read_to_string(&path).is_ok_and(|x| x == contents) {
+            continue;
+        }
+
+        if args.check {
+            stale.push(path.display().to_string());
+        } else {
+            fs::write(&path, contents)?;
+            eprintln!("codegen: wrote {}", path.display());
+        }
+    }
+
+    if !stale.is_empty() {
+        bail!(
+            "out of date, run `cargo run -p codegen`: {}",
+            stale.join(", ")
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use eyre::{Result, bail, eyre};
+
+use crate::schema::{Field, Kind, Schema, Side, screaming};
+
+const START: &str = "<!-- codegen:";
+const END: &str = "<!-- /codegen -->";
+
+/// A table with its columns padded out to line up.
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            rows.iter()
+                .map(|x| x[i].chars().count())
+                .chain([headers[i].len()])
+                .max()
+                .unwrap()
+        })
+        .collect();
+
+    let line = |cells: Vec<String>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - cell.chars().count())))
+            .collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+
+    let mut out = line(headers.iter().map(|x| x.to_string()).collect());
+    out += &line(widths.iter().map(|x| "-".repeat(*x)).collect());
+    for row in rows {
+        out += &line(row.clone());
+    }
+
+    out
+}
+
+fn code(text: &str) -> String {
+    format!("`{text}`")
+}
+
+fn link(name: &str, suffix: &str) -> String {
+    format!("[`{name}{suffix}`](#{})", name.to_lowercase())
+}
+
+fn size(schema: &Schema, kind: &Kind) -> String {
+    schema
+        .size(kind)
+        .map_or_else(|| String::from("variable"), |x| x.to_string())
+}
+
+/// The rows describing a field, which might be more than one if it has a length or a flag.
+fn rows(schema: &Schema, field: &Field) -> Vec<Vec<String>> {
+    let name = &field.name;
+    let row = |ty: String| vec![code(name), ty, size(schema, &field.kind), field.doc.clone()];
+    let len = || {
+        vec![
+            code(&format!("{name}_len")),
+            code("uint32"),
+            String::from("4"),
+            format!("Length of `{name}`."),
+        ]
+    };
+
+    match &field.kind {
+        Kind::Int { bits, .. } => vec![row(code(&format!("uint{bits}")))],
+        Kind::F32 => vec![row(code("float"))],
+        Kind::Bool => vec![row(code("bool"))],
+        Kind::Version => vec![row(code("uint16"))],
+        Kind::Capabilities => vec![row(code("uint32"))],
+        Kind::Str { len, .. } => vec![row(code(&format!("char[{len}]")))],
+        Kind::Bytes { .. } => vec![len(), row(code(&format!("byte[{name}_len]")))],
+        Kind::List { of, .. } => vec![len(), row(link(of, &format!("[{name}_len]")))],
+        Kind::Optional { of, flag, flag_doc } => vec![
+            vec![
+                code(flag),
+                code("bool"),
+                String::from("1"),
+                flag_doc.clone(),
+            ],
+            vec![
+                code(name),
+                link(of, ""),
+                size(schema, &Kind::Struct(of.clone())),
+                field.doc.clone(),
+            ],
+        ],
+        Kind::Enum(enumeration) => {
+            let variants: Vec<String> = schema
+                .enumeration(enumeration)
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(i, x)| match i {
+                    0 => format!("`{}` = 0", screaming(&x.name)),
+                    _ => code(&screaming(&x.name)),
+                })
+                .collect();
+
+            let doc = field.doc.trim_end_matches('.');
+            vec![vec![
+                code(name),
+                code("uint8"),
+                String::from("1"),
+                format!("{doc} ({}).", variants.join(", ")),
+            ]]
+        }
+        Kind::Struct(structure) => vec![row(link(structure, ""))],
+    }
+}
+
+fn fields(schema: &Schema, fields: &[Field]) -> String {
+    if fields.is_empty() {
+        return String::from("No body.\n");
+    }
+
+    let rows: Vec<Vec<String>> = fields.iter().flat_map(|x| rows(schema, x)).collect();
+    table(&["Field", "Type", "Size (bytes)", "Description"], &rows)
+}
+
+fn region(schema: &Schema, name: &str) -> Result<String> {
+    if name == "overview" {
+        let rows: Vec<Vec<String>> = schema
+            .packets
+            .iter()
+            .map(|x| {
+                vec![
+                    format!(
+                        "[`{:#04x}`](#{:#04x}---{})",
+                        x.tag,
+                        x.tag,
+                        x.name.to_lowercase()
+                    ),
+                    code(&x.name),
+                    String::from(match x.from {
+                        Side::Client => "Client → Server",
+                        Side::Server => "Server → Client",
+                    }),
+                    x.summary.clone(),
+                ]
+            })
+            .collect();
+
+        return Ok(table(&["Tag", "Name", "Direction", "Summary"], &rows));
+    }
+
+    if name == "capabilities" {
+        let rows: Vec<Vec<String>> = schema
+            .capabilities
+            .iter()
+            .map(|capability| {
+                let packets: Vec<String> = schema
+                    .sent()
+                    .filter(|x| x.capability.as_ref() == Some(&capability.name))
+                    .map(|x| code(&x.name))
+                    .collect();
+
+                vec![
+                    capability.bit.to_string(),
+                    code(&capability.name),
+                    packets.join(", "),
+                ]
+            })
+            .collect();
+
+        return Ok(table(&["Bit", "Name", "Packets"], &rows));
+    }
+
+    if let Some(packet) = name.strip_prefix("packet:") {
+        let packet = schema
+            .sent()
+            .find(|x| x.name == packet)
+            .ok_or_else(|| eyre!("there's no packet called {packet}"))?;
+
+        return Ok(fields(schema, &packet.fields));
+    }
+
+    if let Some(structure) = name.strip_prefix("struct:") {
+        let structure = schema
+            .structs
+            .iter()
+            .find(|x| x.name == structure)
+            .ok_or_else(|| eyre!("there's no struct called {structure}"))?;
+
+        return Ok(fields(schema, &structure.fields));
+    }
+
+    bail!("unknown region {name}")
+}
+
+/// Fills in every region of `PROTOCOL.md` between `<!-- codegen:NAME -->` and `<!-- /codegen -->`.
+pub fn generate(schema: &Schema, source: &str) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = source;
+    let mut filled = Vec::new();
+
+    while let Some(start) = rest.find(START) {
+        out += &rest[..start];
+        rest = &rest[start..];
+
+        let open = rest.find("-->").ok_or_else(|| eyre!("unclosed comment"))? + 3;
+        let name = rest[START.len()..open - 3].trim();
+        let close = rest
+            .find(END)
+            .ok_or_else(|| eyre!("region {name} never ends"))?;
+
+        out += &rest[..open];
+        out += "\n\n";
+        out += &region(schema, name)?;
+        out += "\n";
+        out += END;
+
+        filled.push(name.to_owned());
+        rest = &rest[close + END.len()..];
+    }
+    out += rest;
+
+    let expected = schema
+        .sent()
+        .map(|x| format!("packet:{}", x.name))
+        .chain(schema.structs.iter().map(|x| format!("struct:{}", x.name)));
+    for name in expected {
+        if !filled.contains(&name) {
+            bail!("PROTOCOL.md is missing a `{START}{name} -->` region");
+        }
+    }
+
+    Ok(out)
+}
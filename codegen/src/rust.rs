@@ -0,0 +1,715 @@
+use std::{
+    fmt::Write as _,
+    io::Write as _,
+    process::{Command, Stdio},
+};
+
+use eyre::{Result, bail};
+
+use crate::schema::{Field, Kind, Packet, Schema, Struct, pascal};
+
+/// How a field is reached while it's being serialized.
+#[derive(Clone, Copy)]
+enum Access {
+    /// A field of `self`.
+    Field,
+
+    /// A reference bound by matching on a packet.
+    Binding,
+
+    /// `self` itself, for structs which are just a list.
+    Whole,
+}
+
+impl Access {
+    /// An expression which methods can be called on.
+    fn place(self, name: &str) -> String {
+        match self {
+            Access::Field => format!("self.{name}"),
+            Access::Binding => name.to_owned(),
+            Access::Whole => String::from("self"),
+        }
+    }
+
+    /// An expression for the value itself, for types which are [`Copy`].
+    fn copy(self, name: &str) -> String {
+        match self {
+            Access::Field => format!("self.{name}"),
+            Access::Binding => format!("*{name}"),
+            Access::Whole => String::from("*self"),
+        }
+    }
+
+    /// An expression for a reference to the value.
+    fn borrow(self, name: &str) -> String {
+        match self {
+            Access::Field => format!("&self.{name}"),
+            Access::Binding => name.to_owned(),
+            Access::Whole => String::from("self"),
+        }
+    }
+}
+
+/// The name which something is referred to by inside of the generated module.
+fn local(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+fn variant(packet: &Packet) -> String {
+    pascal(&packet.name)
+}
+
+/// Links the names of packets in documentation to their variants.
+fn doc(schema: &Schema, text: &str) -> String {
+    let mut doc = text.to_owned();
+    for packet in schema.sent() {
+        let name = format!("`{}`", packet.name);
+        doc = doc.replace(&name, &format!("[`Packet::{}`]", variant(packet)));
+    }
+
+    doc
+}
+
+fn comment(out: &mut String, schema: &Schema, text: Option<&str>) {
+    if let Some(text) = text {
+        writeln!(out, "/// {}", doc(schema, text)).unwrap();
+    }
+}
+
+/// The type of a field in Rust, or [`None`] if it isn't kept around.
+fn ty(schema: &Schema, kind: &Kind) -> Option<String> {
+    let ty = match kind {
+        Kind::Int {
+            rust: Some(rust), ..
+        } => rust.clone(),
+        Kind::Int { bits, .. } => format!("u{bits}"),
+        Kind::F32 => String::from("f32"),
+        Kind::Bool => String::from("bool"),
+        Kind::Version => return None,
+        Kind::Capabilities => String::from("Capabilities"),
+        Kind::Str { .. } => String::from("String"),
+        Kind::Bytes { .. } => String::from("Bytes"),
+        Kind::List { of, .. } => format!("Vec<{}>", schema.structure(of).rust),
+        Kind::Optional { of, .. } => format!("Option<{}>", schema.structure(of).rust),
+        Kind::Enum(name) => local(&schema.enumeration(name).rust).to_owned(),
+        Kind::Struct(name) => schema.structure(name).rust.clone(),
+    };
+
+    Some(ty)
+}
+
+/// Whether reading a field depends on the limits.
+fn limited(kind: &Kind) -> bool {
+    matches!(
+        kind,
+        Kind::Bytes { .. } | Kind::List { .. } | Kind::Optional { .. } | Kind::Struct(_)
+    )
+}
+
+fn len(len: usize, constant: &Option<String>) -> String {
+    constant.clone().unwrap_or_else(|| len.to_string())
+}
+
+/// Which of the two codecs is being generated.
+#[derive(Clone, Copy)]
+enum Mode {
+    /// `Serialize` and `Deserialize`, over tokio's `AsyncWrite` and `AsyncRead`.
+    Async,
+
+    /// `Encode` and `Decode`, over a `BufMut` and `Buf`.
+    Sync,
+}
+
+impl Mode {
+    /// A statement which writes a primitive, like `u32`.
+    fn put(self, ty: &str, value: &str) -> String {
+        match self {
+            Mode::Async => format!("writer.write_{ty}({value}).await?;"),
+            Mode::Sync => format!("buf.put_{ty}({value});"),
+        }
+    }
+
+    /// A statement which writes a slice of bytes.
+    fn slice(self, value: &str) -> String {
+        match self {
+            Mode::Async => format!("writer.write_all({value}).await?;"),
+            Mode::Sync => format!("buf.put_slice({value});"),
+        }
+    }
+
+    /// A statement which writes `len` zeroes.
+    fn zeroes(self, len: usize) -> String {
+        match self {
+            Mode::Async => format!("writer.write_all(&[0; {len}]).await?"),
+            Mode::Sync => format!("buf.put_bytes(0, {len})"),
+        }
+    }
+
+    /// A statement which writes a value that has its own impl.
+    fn nested(self, place: &str) -> String {
+        match self {
+            Mode::Async => format!("{place}.serialize(writer).await?"),
+            Mode::Sync => format!("{place}.encode(buf)"),
+        }
+    }
+
+    /// An expression which reads a primitive, like `u32`.
+    fn get(self, ty: &str) -> String {
+        match self {
+            Mode::Async => format!("reader.read_{ty}().await?"),
+            Mode::Sync => format!("buf.try_get_{ty}()?"),
+        }
+    }
+
+    /// An expression which calls one of the helpers, like `list`, with the reader first.
+    fn call(self, function: &str, args: &str) -> String {
+        match self {
+            Mode::Async => format!("{function}(reader{args}).await?"),
+            Mode::Sync => format!("{function}(buf{args})?"),
+        }
+    }
+
+    /// The type parameter of whatever's being read from.
+    fn reader(self) -> &'static str {
+        match self {
+            Mode::Async => "R",
+            Mode::Sync => "B",
+        }
+    }
+
+    /// The method which reads a value that has its own impl.
+    fn decode(self) -> &'static str {
+        match self {
+            Mode::Async => "deserialize_with",
+            Mode::Sync => "decode_with",
+        }
+    }
+}
+
+/// Statements which write a field.
+fn write(schema: &Schema, field: &Field, access: Access, mode: Mode) -> String {
+    let name = &field.name;
+    let place = access.place(name);
+    let copy = access.copy(name);
+    let borrow = access.borrow(name);
+
+    match &field.kind {
+        Kind::Int {
+            bits,
+            rust: Some(_),
+        } => mode.put(&format!("u{bits}"), &format!("{copy} as u{bits}")),
+        Kind::Int { bits, rust: None } => mode.put(&format!("u{bits}"), &copy),
+        Kind::F32 => mode.put("f32", &copy),
+        Kind::Bool | Kind::Enum(_) => mode.put("u8", &format!("{copy} as u8")),
+        Kind::Version => mode.put("u16", "PROTOCOL_VERSION"),
+        Kind::Capabilities => mode.put("u32", &format!("{place}.0")),
+        Kind::Str {
+            len: size,
+            constant,
+        } => format!(
+            "let {name}: [u8; {}] = {place}.fixed();\n{}",
+            len(*size, constant),
+            mode.slice(&format!("&{name}"))
+        ),
+        Kind::Bytes { .. } => format!(
+            "{}\n{}",
+            mode.put("u32", &format!("{place}.len() as u32")),
+            mode.slice(&borrow)
+        ),
+        Kind::List { .. } => format!(
+            "{}\nfor item in {borrow} {{ {}; }}",
+            mode.put("u32", &format!("{place}.len() as u32")),
+            mode.nested("item")
+        ),
+        Kind::Optional { of, .. } => format!(
+            "{}\n\
+             match {borrow} {{\n\
+                 Some(x) => {},\n\
+                 None => {},\n\
+             }}",
+            mode.put("u8", &format!("{place}.is_some() as u8")),
+            mode.nested("x"),
+            mode.zeroes(schema.size(&Kind::Struct(of.clone())).unwrap())
+        ),
+        Kind::Struct(_) => format!("{};", mode.nested(&place)),
+    }
+}
+
+/// An expression which reads a field.
+fn read(schema: &Schema, field: &Field, mode: Mode) -> String {
+    let reader = mode.reader();
+
+    match &field.kind {
+        Kind::Int {
+            bits,
+            rust: Some(rust),
+        } => format!("{} as {rust}", mode.get(&format!("u{bits}"))),
+        Kind::Int { bits, rust: None } => mode.get(&format!("u{bits}")),
+        Kind::F32 => mode.get("f32"),
+        Kind::Bool => format!("{} != 0", mode.get("u8")),
+        Kind::Version => unreachable!("the version is checked before anything is read"),
+        Kind::Capabilities => format!("Capabilities({})", mode.get("u32")),
+        Kind::Str {
+            len: size,
+            constant,
+        } => mode.call(&format!("fixed::<{reader}, {}>", len(*size, constant)), ""),
+        Kind::Bytes { limit } => {
+            mode.call("bytes", &format!(", \"{}\", limits.{limit}", field.name))
+        }
+        Kind::List { of, limit, what } => mode.call(
+            &format!("list::<{reader}, {}>", schema.structure(of).rust),
+            &format!(", \"{what}\", limits.{limit}, limits"),
+        ),
+        Kind::Optional { of, .. } => mode.call(
+            &format!("optional::<{reader}, {}>", schema.structure(of).rust),
+            &format!(
+                ", {}, limits",
+                schema.size(&Kind::Struct(of.clone())).unwrap()
+            ),
+        ),
+        Kind::Enum(name) => format!(
+            "{}::try_from({})?",
+            local(&schema.enumeration(name).rust),
+            mode.get("u8")
+        ),
+        Kind::Struct(name) => mode.call(
+            &format!("{}::{}", schema.structure(name).rust, mode.decode()),
+            ", limits",
+        ),
+    }
+}
+
+fn fields(fields: &[Field]) -> impl Iterator<Item = &Field> {
+    fields.iter().filter(|x| !matches!(x.kind, Kind::Version))
+}
+
+/// The helpers from `sync` and `deserializers` which the generated code calls.
+fn helpers(schema: &Schema) -> Vec<&'static str> {
+    let kinds: Vec<&Kind> = schema
+        .structs
+        .iter()
+        .flat_map(|x| &x.fields)
+        .chain(schema.sent().flat_map(|x| &x.fields))
+        .map(|x| &x.kind)
+        .collect();
+    let uses = |f: fn(&Kind) -> bool| kinds.iter().any(|x| f(x));
+
+    let mut helpers = Vec::new();
+    if uses(|x| matches!(x, Kind::Str { .. })) {
+        helpers.push("fixed");
+    }
+    if uses(|x| matches!(x, Kind::Bytes { .. })) {
+        helpers.push("bytes");
+    }
+    if uses(|x| matches!(x, Kind::List { .. })) {
+        helpers.push("list");
+    }
+    if uses(|x| matches!(x, Kind::Optional { .. })) {
+        helpers.push("optional");
+    }
+    if uses(|x| matches!(x, Kind::Version)) {
+        helpers.push("version");
+    }
+
+    helpers
+}
+
+fn imports(schema: &Schema, out: &mut String) {
+    let mut names = vec!["Capabilities", "Error", "Limits"];
+    for structure in &schema.structs {
+        match structure.rust.split_once("::") {
+            Some((module, _)) => names.push(module),
+            None => names.push(&structure.rust),
+        }
+    }
+
+    names.sort();
+    names.dedup();
+
+    let helpers = helpers(schema);
+    let mut sync = vec!["Decode", "Encode"];
+    if helpers.contains(&"fixed") {
+        sync.push("ToFixed");
+    }
+    sync.extend(&helpers);
+
+    // Only the packets' own fields are declared in here, the structs are in `lib.rs`.
+    let types: Vec<String> = schema
+        .sent()
+        .flat_map(|x| &x.fields)
+        .filter_map(|x| ty(schema, &x.kind))
+        .collect();
+    let mut alloc = Vec::new();
+    if types.iter().any(|x| x == "String") {
+        alloc.push("string::String");
+    }
+    if types.iter().any(|x| x.starts_with("Vec<")) {
+        alloc.push("vec::Vec");
+    }
+
+    if !alloc.is_empty() {
+        writeln!(out, "use alloc::{{{}}};\n", alloc.join(", ")).unwrap();
+    }
+    writeln!(out, "use bytes::{{Buf, BufMut, Bytes}};\n").unwrap();
+    writeln!(
+        out,
+        "use crate::{{{}, sync::{{{}}}}};\n",
+        names.join(", "),
+        sync.join(", ")
+    )
+    .unwrap();
+}
+
+fn constants(schema: &Schema, out: &mut String) {
+    let handshake: Vec<String> = schema
+        .sent()
+        .filter(|x| x.fields.iter().any(|x| matches!(x.kind, Kind::Version)))
+        .map(|x| format!("[`Packet::{}`]", variant(x)))
+        .collect();
+
+    writeln!(
+        out,
+        "/// Version of the protocol, which is checked in {}.\n\
+         /// This should be bumped whenever the layout of an existing packet changes.\n\
+         pub const PROTOCOL_VERSION: u16 = {};\n",
+        handshake.join(" and "),
+        schema.version
+    )
+    .unwrap();
+
+    for constant in &schema.constants {
+        comment(out, schema, Some(&constant.doc));
+        writeln!(
+            out,
+            "pub const {}: usize = {};\n",
+            constant.name, constant.value
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "impl Capabilities {{").unwrap();
+    let mut all = 0u32;
+    for capability in &schema.capabilities {
+        comment(out, schema, Some(&capability.doc));
+        writeln!(
+            out,
+            "pub const {}: Self = Self(1 << {});\n",
+            capability.name, capability.bit
+        )
+        .unwrap();
+        all |= 1 << capability.bit;
+    }
+    writeln!(out, "pub const ALL: Self = Self({all:#b});\n}}\n").unwrap();
+}
+
+fn enums(schema: &Schema, out: &mut String) {
+    for enumeration in &schema.enums {
+        let name = local(&enumeration.rust);
+        let default = if enumeration.default.is_some() {
+            ", Default"
+        } else {
+            ""
+        };
+
+        comment(out, schema, enumeration.doc.as_deref());
+        writeln!(
+            out,
+            "#[derive(Debug, PartialEq, Clone, Copy{default})]\n#[repr(u8)]\npub enum {name} {{"
+        )
+        .unwrap();
+
+        for (i, variant) in enumeration.variants.iter().enumerate() {
+            comment(out, schema, variant.doc.as_deref());
+            if enumeration.default.as_ref() == Some(&variant.name) {
+                writeln!(out, "#[default]").unwrap();
+            }
+
+            match i {
+                0 => writeln!(out, "{} = 0,", variant.name).unwrap(),
+                _ => writeln!(out, "{},", variant.name).unwrap(),
+            }
+
+            if variant.doc.is_some() {
+                writeln!(out).unwrap();
+            }
+        }
+        writeln!(out, "}}\n").unwrap();
+
+        writeln!(
+            out,
+            "impl TryFrom<u8> for {name} {{\n\
+             type Error = Error;\n\n\
+             fn try_from(value: u8) -> Result<Self, Self::Error> {{\n\
+             match value {{"
+        )
+        .unwrap();
+        for (i, variant) in enumeration.variants.iter().enumerate() {
+            writeln!(out, "{i} => Ok({name}::{}),", variant.name).unwrap();
+        }
+        writeln!(
+            out,
+            "other => Err(Error::Invalid(\"{}\", other)),\n}}\n}}\n}}\n",
+            crate::schema::words(&enumeration.name).join(" ")
+        )
+        .unwrap();
+    }
+}
+
+/// A pattern matching a packet, binding all of its fields.
+fn pattern(packet: &Packet, bind: bool) -> String {
+    let names: Vec<&str> = fields(&packet.fields).map(|x| x.name.as_str()).collect();
+    match (names.is_empty(), bind) {
+        (true, _) => format!("Packet::{}", variant(packet)),
+        (false, true) => format!("Packet::{} {{ {} }}", variant(packet), names.join(", ")),
+        (false, false) => format!("Packet::{} {{ .. }}", variant(packet)),
+    }
+}
+
+fn packets(schema: &Schema, out: &mut String) {
+    writeln!(
+        out,
+        "#[derive(Debug, PartialEq, Clone)]\npub enum Packet {{"
+    )
+    .unwrap();
+    for packet in schema.sent() {
+        comment(out, schema, Some(&packet.summary));
+
+        let fields: Vec<&Field> = fields(&packet.fields).collect();
+        if fields.is_empty() {
+            writeln!(out, "{},", variant(packet)).unwrap();
+            continue;
+        }
+
+        writeln!(out, "{} {{", variant(packet)).unwrap();
+        for field in fields {
+            comment(out, schema, Some(&field.doc));
+            writeln!(out, "{}: {},", field.name, ty(schema, &field.kind).unwrap()).unwrap();
+        }
+        writeln!(out, "}},").unwrap();
+    }
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(
+        out,
+        "impl Packet {{\npub fn tag(&self) -> u8 {{\nmatch self {{"
+    )
+    .unwrap();
+    for packet in schema.sent() {
+        writeln!(out, "{} => {},", pattern(packet, false), packet.tag).unwrap();
+    }
+    writeln!(out, "}}\n}}\n").unwrap();
+
+    writeln!(
+        out,
+        "/// The capability which a client needs to be sent this packet, if any.\n\
+         pub fn capability(&self) -> Option<Capabilities> {{\nmatch self {{"
+    )
+    .unwrap();
+    for capability in &schema.capabilities {
+        let patterns: Vec<String> = schema
+            .sent()
+            .filter(|x| x.capability.as_ref() == Some(&capability.name))
+            .map(|x| pattern(x, false))
+            .collect();
+
+        if !patterns.is_empty() {
+            writeln!(
+                out,
+                "{} => Some(Capabilities::{}),",
+                patterns.join(" | "),
+                capability.name
+            )
+            .unwrap();
+        }
+    }
+    writeln!(out, "_ => None,\n}}\n}}\n}}\n").unwrap();
+}
+
+fn serialize(schema: &Schema, structure: &Struct, mode: Mode, out: &mut String) {
+    match mode {
+        Mode::Async => writeln!(
+            out,
+            "impl<W: AsyncWrite + Unpin + Send> Serialize<W> for {} {{\n\
+             async fn serialize(&self, writer: &mut W) -> Result<(), Error> {{",
+            structure.rust
+        ),
+        Mode::Sync => writeln!(
+            out,
+            "impl Encode for {} {{\nfn encode<B: BufMut>(&self, buf: &mut B) {{",
+            structure.rust
+        ),
+    }
+    .unwrap();
+
+    let access = if structure.list {
+        Access::Whole
+    } else {
+        Access::Field
+    };
+    for field in &structure.fields {
+        writeln!(out, "{}", write(schema, field, access, mode)).unwrap();
+    }
+
+    match mode {
+        Mode::Async => writeln!(out, "Ok(())\n}}\n}}\n").unwrap(),
+        Mode::Sync => writeln!(out, "}}\n}}\n").unwrap(),
+    }
+}
+
+/// The start of an impl of `Deserialize` or `Decode`, up to the body of the function.
+fn header(mode: Mode, ty: &str, limits: &str) -> String {
+    match mode {
+        Mode::Async => format!(
+            "impl<R: AsyncRead + Unpin + Send> Deserialize<R> for {ty} {{\n\
+             async fn deserialize_with(reader: &mut R, {limits}: Limits) -> Result<Self, Error> {{"
+        ),
+        Mode::Sync => format!(
+            "impl Decode for {ty} {{\n\
+             fn decode_with<B: Buf>(buf: &mut B, {limits}: Limits) -> Result<Self, Error> {{"
+        ),
+    }
+}
+
+fn deserialize(schema: &Schema, structure: &Struct, mode: Mode, out: &mut String) {
+    let limits = if structure.fields.iter().any(|x| limited(&x.kind)) {
+        "limits"
+    } else {
+        "_"
+    };
+
+    writeln!(out, "{}", header(mode, &structure.rust, limits)).unwrap();
+
+    if structure.list {
+        writeln!(
+            out,
+            "Ok(Self::from({}))",
+            read(schema, &structure.fields[0], mode)
+        )
+        .unwrap();
+    } else {
+        writeln!(out, "Ok(Self {{").unwrap();
+        for field in &structure.fields {
+            writeln!(out, "{}: {},", field.name, read(schema, field, mode)).unwrap();
+        }
+        writeln!(out, "}})").unwrap();
+    }
+    writeln!(out, "}}\n}}\n").unwrap();
+}
+
+fn codec(schema: &Schema, mode: Mode, out: &mut String) {
+    for structure in &schema.structs {
+        serialize(schema, structure, mode, out);
+        deserialize(schema, structure, mode, out);
+    }
+
+    match mode {
+        Mode::Async => writeln!(
+            out,
+            "impl<W: AsyncWrite + Unpin + Send> Serialize<W> for Packet {{\n\
+             async fn serialize(&self, writer: &mut W) -> Result<(), Error> {{"
+        ),
+        Mode::Sync => writeln!(
+            out,
+            "impl Encode for Packet {{\nfn encode<B: BufMut>(&self, buf: &mut B) {{"
+        ),
+    }
+    .unwrap();
+    writeln!(out, "{}\n\nmatch self {{", mode.put("u8", "self.tag()")).unwrap();
+    for packet in schema.sent() {
+        writeln!(out, "{} => {{", pattern(packet, true)).unwrap();
+        for field in &packet.fields {
+            writeln!(out, "{}", write(schema, field, Access::Binding, mode)).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+    }
+    match mode {
+        Mode::Async => writeln!(out, "}}\n\nwriter.flush().await?;\nOk(())\n}}\n}}\n").unwrap(),
+        Mode::Sync => writeln!(out, "}}\n}}\n}}\n").unwrap(),
+    }
+
+    writeln!(
+        out,
+        "{}\nmatch {} {{",
+        header(mode, "Packet", "limits"),
+        mode.get("u8")
+    )
+    .unwrap();
+    for packet in schema.sent() {
+        let fields: Vec<&Field> = fields(&packet.fields).collect();
+        let value = if fields.is_empty() {
+            format!("Self::{}", variant(packet))
+        } else {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|x| format!("{}: {}", x.name, read(schema, x, mode)))
+                .collect();
+            format!("Self::{} {{ {} }}", variant(packet), fields.join(", "))
+        };
+
+        // Nothing else is read if the version doesn't match.
+        if packet.fields.len() > fields.len() {
+            writeln!(
+                out,
+                "{} => {{\n{};\nOk({value})\n}}",
+                packet.tag,
+                mode.call("version", "")
+            )
+            .unwrap();
+        } else {
+            writeln!(out, "{} => Ok({value}),", packet.tag).unwrap();
+        }
+    }
+    writeln!(out, "tag => Err(Error::Unknown(tag)),\n}}\n}}\n}}").unwrap();
+}
+
+/// The async codec, which needs tokio and so isn't there without the `std` feature.
+fn io(schema: &Schema, out: &mut String) {
+    let mut deserializers = vec!["Deserialize"];
+    deserializers.extend(helpers(schema));
+
+    writeln!(
+        out,
+        "\n#[cfg(feature = \"std\")]\n\
+         mod io {{\n\
+         use tokio::io::{{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt}};\n\n\
+         use super::*;\n\
+         use crate::{{deserializers::{{{}}}, serializers::Serialize}};\n",
+        deserializers.join(", ")
+    )
+    .unwrap();
+    codec(schema, Mode::Async, out);
+    writeln!(out, "}}").unwrap();
+}
+
+/// Generates `shared/src/codec.rs`.
+pub fn generate(schema: &Schema) -> Result<String> {
+    let mut out = String::from(
+        "//! Generated from `protocol.json` by `cargo run -p codegen`, so don't edit this by hand.\n\n",
+    );
+
+    imports(schema, &mut out);
+    constants(schema, &mut out);
+    enums(schema, &mut out);
+    packets(schema, &mut out);
+    codec(schema, Mode::Sync, &mut out);
+    io(schema, &mut out);
+
+    format(&out)
+}
+
+fn format(source: &str) -> Result<String> {
+    let mut rustfmt = Command::new("rustfmt")
+        .args(["--edition", "2024", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    rustfmt.stdin.take().unwrap().write_all(source.as_bytes())?;
+
+    let output = rustfmt.wait_with_output()?;
+    if !output.status.success() {
+        bail!("rustfmt failed on the generated code");
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
@@ -0,0 +1,407 @@
+use eyre::{Result, bail, eyre};
+use json::JsonValue;
+
+/// The whole protocol, as described by `protocol.json`.
+pub struct Schema {
+    pub version: u16,
+    pub constants: Vec<Constant>,
+    pub capabilities: Vec<Capability>,
+    pub enums: Vec<Enum>,
+    pub structs: Vec<Struct>,
+    pub packets: Vec<Packet>,
+}
+
+pub struct Constant {
+    pub name: String,
+    pub value: usize,
+    pub doc: String,
+}
+
+pub struct Capability {
+    pub name: String,
+    pub bit: u32,
+    pub doc: String,
+}
+
+/// A one byte enum, numbered from zero in the order of its variants.
+pub struct Enum {
+    /// Name in C and in `PROTOCOL.md`.
+    pub name: String,
+
+    /// Path of the enum in the shared crate, relative to its root.
+    pub rust: String,
+
+    /// What the names of the variants start with in C.
+    pub prefix: String,
+    pub doc: Option<String>,
+    pub default: Option<String>,
+    pub variants: Vec<Variant>,
+}
+
+pub struct Variant {
+    pub name: String,
+    pub doc: Option<String>,
+}
+
+pub struct Struct {
+    pub name: String,
+    pub rust: String,
+
+    /// Whether this is just a wrapper around its one list field,
+    /// which is built with `From<Vec<_>>` instead of field by field.
+    pub list: bool,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Side {
+    Client,
+    Server,
+}
+
+pub struct Packet {
+    pub tag: u8,
+    pub name: String,
+    pub from: Side,
+
+    /// Tags which are only documented, and never actually sent.
+    pub reserved: bool,
+    pub capability: Option<String>,
+    pub summary: String,
+    pub fields: Vec<Field>,
+}
+
+pub struct Field {
+    pub name: String,
+    pub doc: String,
+    pub kind: Kind,
+}
+
+pub enum Kind {
+    /// An unsigned integer, which might be a different type in Rust.
+    Int {
+        bits: u32,
+        rust: Option<String>,
+    },
+    F32,
+    Bool,
+
+    /// The protocol version, which isn't a field in Rust.
+    Version,
+    Capabilities,
+
+    /// A fixed length, null padded string.
+    Str {
+        len: usize,
+        constant: Option<String>,
+    },
+
+    /// A `u32` length followed by that many bytes.
+    Bytes {
+        limit: String,
+    },
+
+    /// A `u32` length followed by that many structs.
+    List {
+        of: String,
+        limit: String,
+        what: String,
+    },
+
+    /// A flag followed by a struct, which is all zeros if the flag isn't set.
+    Optional {
+        of: String,
+        flag: String,
+        flag_doc: String,
+    },
+    Enum(String),
+    Struct(String),
+}
+
+fn string(value: &JsonValue, key: &str) -> Result<String> {
+    value[key]
+        .as_str()
+        .map(str::to_owned)
+        .ok_or_else(|| eyre!("missing string `{key}` in {value}"))
+}
+
+fn optional(value: &JsonValue, key: &str) -> Option<String> {
+    value[key].as_str().map(str::to_owned)
+}
+
+fn number(value: &JsonValue, key: &str) -> Result<usize> {
+    value[key]
+        .as_usize()
+        .ok_or_else(|| eyre!("missing number `{key}` in {value}"))
+}
+
+fn array<T>(
+    value: &JsonValue,
+    key: &str,
+    parse: impl Fn(&JsonValue) -> Result<T>,
+) -> Result<Vec<T>> {
+    if !value[key].is_array() {
+        bail!("missing array `{key}` in {value}");
+    }
+
+    value[key].members().map(parse).collect()
+}
+
+impl Schema {
+    pub fn parse(source: &str) -> Result<Self> {
+        let json = json::parse(source)?;
+
+        let constants = array(&json, "constants", |x| {
+            Ok(Constant {
+                name: string(x, "name")?,
+                value: number(x, "value")?,
+                doc: string(x, "doc")?,
+            })
+        })?;
+
+        let capabilities = array(&json, "capabilities", |x| {
+            Ok(Capability {
+                name: string(x, "name")?,
+                bit: number(x, "bit")? as u32,
+                doc: string(x, "doc")?,
+            })
+        })?;
+
+        let enums = array(&json, "enums", |x| {
+            let name = string(x, "name")?;
+            Ok(Enum {
+                rust: optional(x, "rust").unwrap_or_else(|| name.clone()),
+                prefix: string(x, "prefix")?,
+                doc: optional(x, "doc"),
+                default: optional(x, "default"),
+                variants: array(x, "variants", |x| {
+                    Ok(Variant {
+                        name: string(x, "name")?,
+                        doc: optional(x, "doc"),
+                    })
+                })?,
+                name,
+            })
+        })?;
+
+        let field = |x: &JsonValue| -> Result<Field> {
+            let ty = string(x, "type")?;
+            let kind = match ty.as_str() {
+                "u8" | "u16" | "u32" | "u64" => Kind::Int {
+                    bits: ty[1..].parse()?,
+                    rust: optional(x, "rust"),
+                },
+                "f32" => Kind::F32,
+                "bool" => Kind::Bool,
+                "version" => Kind::Version,
+                "capabilities" => Kind::Capabilities,
+                "string" => match x["len"].as_str() {
+                    Some(constant) => Kind::Str {
+                        len: constants
+                            .iter()
+                            .find(|x| x.name == constant)
+                            .ok_or_else(|| eyre!("unknown constant {constant}"))?
+                            .value,
+                        constant: Some(constant.to_owned()),
+                    },
+                    None => Kind::Str {
+                        len: number(x, "len")?,
+                        constant: None,
+                    },
+                },
+                "bytes" => Kind::Bytes {
+                    limit: string(x, "limit")?,
+                },
+                "list" => Kind::List {
+                    of: string(x, "of")?,
+                    limit: string(x, "limit")?,
+                    what: optional(x, "what").map_or_else(|| string(x, "name"), Ok)?,
+                },
+                "optional" => Kind::Optional {
+                    of: string(x, "of")?,
+                    flag: string(x, "flag")?,
+                    flag_doc: string(x, "flag_doc")?,
+                },
+                _ if enums.iter().any(|x| x.name == ty) => Kind::Enum(ty),
+                _ => Kind::Struct(ty),
+            };
+
+            Ok(Field {
+                name: string(x, "name")?,
+                doc: string(x, "doc")?,
+                kind,
+            })
+        };
+
+        let structs = array(&json, "structs", |x| {
+            let name = string(x, "name")?;
+            Ok(Struct {
+                rust: optional(x, "rust").unwrap_or_else(|| name.clone()),
+                list: x["list"].as_bool().unwrap_or(false),
+                fields: array(x, "fields", field)?,
+                name,
+            })
+        })?;
+
+        let packets = array(&json, "packets", |x| {
+            Ok(Packet {
+                tag: number(x, "tag")? as u8,
+                name: string(x, "name")?,
+                from: match string(x, "from")?.as_str() {
+                    "client" => Side::Client,
+                    "server" => Side::Server,
+                    other => bail!("unknown side {other}"),
+                },
+                reserved: x["reserved"].as_bool().unwrap_or(false),
+                capability: optional(x, "capability"),
+                summary: string(x, "summary")?,
+                fields: array(x, "fields", field)?,
+            })
+        })?;
+
+        let schema = Self {
+            version: number(&json, "version")? as u16,
+            constants,
+            capabilities,
+            enums,
+            structs,
+            packets,
+        };
+        schema.validate()?;
+
+        Ok(schema)
+    }
+
+    /// Makes sure that everything which is referred to actually exists.
+    fn validate(&self) -> Result<()> {
+        for (i, packet) in self.packets.iter().enumerate() {
+            if self.packets[..i].iter().any(|x| x.tag == packet.tag) {
+                bail!("{} reuses tag {}", packet.name, packet.tag);
+            }
+
+            if let Some(capability) = &packet.capability
+                && !self.capabilities.iter().any(|x| &x.name == capability)
+            {
+                bail!("{} needs unknown capability {capability}", packet.name);
+            }
+
+            if packet.reserved && !packet.fields.is_empty() {
+                bail!("{} is reserved, so it can't have fields", packet.name);
+            }
+
+            // Nothing past a version which doesn't match can be read.
+            let version = packet
+                .fields
+                .iter()
+                .position(|x| matches!(x.kind, Kind::Version));
+            if version.is_some_and(|x| x != 0) {
+                bail!("{} has to start with its version", packet.name);
+            }
+        }
+
+        let fields = self.structs.iter().flat_map(|x| &x.fields);
+        for field in fields.chain(self.packets.iter().flat_map(|x| &x.fields)) {
+            let (Kind::Struct(name)
+            | Kind::List { of: name, .. }
+            | Kind::Optional { of: name, .. }) = &field.kind
+            else {
+                continue;
+            };
+
+            if !self.structs.iter().any(|x| &x.name == name) {
+                bail!("{} has unknown type {name}", field.name);
+            }
+        }
+
+        for structure in &self.structs {
+            if structure
+                .fields
+                .iter()
+                .any(|x| matches!(x.kind, Kind::Version))
+            {
+                bail!(
+                    "{} has a version, which only packets can have",
+                    structure.name
+                );
+            }
+
+            if structure.list
+                && !matches!(
+                    structure.fields[..],
+                    [Field {
+                        kind: Kind::List { .. },
+                        ..
+                    }]
+                )
+            {
+                bail!("{} has to have exactly one list field", structure.name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Packets which are actually sent, excluding reserved tags.
+    pub fn sent(&self) -> impl Iterator<Item = &Packet> {
+        self.packets.iter().filter(|x| !x.reserved)
+    }
+
+    pub fn enumeration(&self, name: &str) -> &Enum {
+        self.enums.iter().find(|x| x.name == name).unwrap()
+    }
+
+    pub fn structure(&self, name: &str) -> &Struct {
+        self.structs.iter().find(|x| x.name == name).unwrap()
+    }
+
+    /// The size of a field on the wire in bytes, or [`None`] if it varies.
+    pub fn size(&self, kind: &Kind) -> Option<usize> {
+        match kind {
+            Kind::Int { bits, .. } => Some(*bits as usize / 8),
+            Kind::F32 | Kind::Capabilities => Some(4),
+            Kind::Bool | Kind::Enum(_) => Some(1),
+            Kind::Version => Some(2),
+            Kind::Str { len, .. } => Some(*len),
+            Kind::Bytes { .. } | Kind::List { .. } => None,
+            Kind::Optional { of, .. } => Some(1 + self.size(&Kind::Struct(of.clone()))?),
+            Kind::Struct(name) => self
+                .structure(name)
+                .fields
+                .iter()
+                .map(|x| self.size(&x.kind))
+                .sum(),
+        }
+    }
+}
+
+/// Turns `SNAKE_CASE` into `PascalCase`.
+pub fn pascal(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().map(|x| x.to_ascii_uppercase());
+            first
+                .into_iter()
+                .chain(chars.map(|x| x.to_ascii_lowercase()))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+/// Splits `PascalCase` into its lowercase words.
+pub fn words(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for char in name.chars() {
+        match words.last_mut() {
+            Some(word) if !char.is_ascii_uppercase() => word.push(char),
+            _ => words.push(char.to_ascii_lowercase().to_string()),
+        }
+    }
+
+    words
+}
+
+/// Turns `PascalCase` into `SCREAMING_CASE`.
+pub fn screaming(name: &str) -> String {
+    words(name).join("_").to_ascii_uppercase()
+}
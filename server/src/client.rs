@@ -1,31 +1,75 @@
 use shared::{
-    BufferedSplitExt, ClientOptions, Packet, PacketReadExt, PacketWriteExt, Reader, Writer,
+    BufferedSplitExt, Capabilities, ClientOptions, Display, Packet, PacketReadExt, PacketWriteExt,
+    Reader, Writer,
 };
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
 use tokio::{io::AsyncWriteExt, net::TcpStream, sync::mpsc, task::JoinHandle};
 
-use crate::{Error, Message};
+use crate::{Error, Message, images::Format};
+
+/// What's kept of a client which disconnected in the middle of a game, so that it can resume.
+#[derive(Debug)]
+pub struct Session {
+    pub id: usize,
+    pub token: u64,
+    pub options: ClientOptions,
+    pub capabilities: Capabilities,
+    pub display: Display,
+}
 
 pub struct Client {
     pub id: usize,
     pub ready: bool,
     pub options: Option<ClientOptions>,
+
+    /// Optional features that the client supports, from its [`Packet::Init`].
+    pub capabilities: Capabilities,
+
+    /// What images the client wants, also from its [`Packet::Init`].
+    pub display: Display,
+
+    /// Secret which is handed out in [`Packet::Confirmed`] to resume the session with.
+    pub token: u64,
+
+    /// What's left of the last progressive image, which goes out a [`Packet::Rows`] at a time.
+    pub rows: VecDeque<Packet>,
     writer: Writer,
     handle: JoinHandle<Result<(), Error>>,
 
+    /// The id which the listener tags packets with, which changes when a session is resumed.
+    listener_id: Arc<AtomicUsize>,
+
     #[allow(dead_code)]
     tx: mpsc::Sender<Message>,
 }
 
 impl Client {
+    /// How the client wants its images, from what it said in its [`Packet::Init`].
+    pub fn format(&self) -> Format {
+        Format {
+            display: self.display,
+            rle: self.capabilities.contains(Capabilities::RLE),
+            progressive: self.capabilities.contains(Capabilities::PROGRESSIVE),
+        }
+    }
+
     async fn listener(
-        id: usize,
+        id: Arc<AtomicUsize>,
         tx: mpsc::Sender<Message>,
         mut reader: Reader,
     ) -> Result<(), Error> {
         loop {
             let packet = reader.read_packet().await;
             let is_err = packet.is_err();
-            tx.send(Message::Packet(id, packet)).await?;
+            tx.send(Message::Packet(id.load(Ordering::Relaxed), packet))
+                .await?;
 
             if is_err {
                 break;
@@ -35,7 +79,7 @@ impl Client {
         Ok(())
     }
 
-    pub async fn write(&mut self, packet: Packet) -> Result<(), Error> {
+    pub async fn write(&mut self, packet: Packet) -> Result<(), shared::Error> {
         self.writer.write_packet(packet).await?;
         self.writer.flush().await?;
         Ok(())
@@ -45,19 +89,49 @@ impl Client {
         self.options.is_some()
     }
 
+    /// Takes over a previous session, including its id. The token isn't reused, so that
+    /// it can only ever be used to resume once.
+    pub fn resume(&mut self, session: Session) {
+        self.id = session.id;
+        self.options = Some(session.options);
+        self.capabilities = session.capabilities;
+        self.display = session.display;
+        self.listener_id.store(session.id, Ordering::Relaxed);
+    }
+
+    /// Gives up the connection, leaving only what's needed to resume later.
+    pub async fn session(self) -> Option<Session> {
+        let session = Session {
+            id: self.id,
+            token: self.token,
+            options: self.options.clone()?,
+            capabilities: self.capabilities,
+            display: self.display,
+        };
+
+        self.close().await;
+        Some(session)
+    }
+
     pub async fn new(
         id: usize,
         tx: mpsc::Sender<Message>,
         socket: TcpStream,
     ) -> Result<Self, Error> {
         let (reader, writer) = socket.buffered_split();
-        let handle = tokio::spawn(Self::listener(id, tx.clone(), reader));
+        let listener_id = Arc::new(AtomicUsize::new(id));
+        let handle = tokio::spawn(Self::listener(listener_id.clone(), tx.clone(), reader));
 
         Ok(Self {
             handle,
             id,
+            token: rand::random(),
+            rows: VecDeque::new(),
+            listener_id,
             tx,
             options: None,
+            capabilities: Capabilities::NONE,
+            display: Display::default(),
             ready: false,
             writer,
         })
@@ -1,4 +1,4 @@
-use std::{num::ParseFloatError, str::Utf8Error};
+use std::{num::ParseFloatError, path::PathBuf, str::Utf8Error};
 
 use tokio::{io, sync::mpsc::error::SendError};
 
@@ -29,4 +29,22 @@ pub enum Error {
 
     #[error("image editing failed")]
     Image(#[from] image::ImageError),
+
+    #[error("invalid image data")]
+    Pixels(#[from] shared::image::Error),
+
+    #[error("background task failed")]
+    Task(#[from] tokio::task::JoinError),
+
+    #[error("invalid image metadata")]
+    Metadata,
+
+    #[error("no image was fetched in time")]
+    Unavailable,
+
+    #[error("no images found in {0}")]
+    NoImages(PathBuf),
+
+    #[error("invalid bundle: {0}")]
+    Bundle(&'static str),
 }
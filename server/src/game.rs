@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use shared::{BestGuess, GameResult, RoundResult, Standing};
+
+/// How far off a missed round counts as, which is as far as a guess can be, being halfway around
+/// the world. It's the worst a guess could have done, so that not guessing never wins a tie.
+pub const MISSED: f32 = std::f32::consts::PI * 6371.0;
+
+/// Statistics which are kept track of over the course of an entire game.
+#[derive(Debug, Default)]
+pub struct Game {
+    /// The combined distance of every guess per player, in kilometers, including missed rounds.
+    pub distances: HashMap<usize, f32>,
+    pub best: Option<BestGuess>,
+}
+
+impl Game {
+    /// Records how far off a player's guess was for a round.
+    pub fn record(&mut self, id: usize, round: usize, distance: f32) {
+        *self.distances.entry(id).or_default() += distance;
+
+        if self.best.is_none_or(|best| distance < best.distance) {
+            self.best = Some(BestGuess {
+                id,
+                round,
+                distance,
+            });
+        }
+    }
+
+    /// Records that a player didn't guess in time for a round.
+    pub fn miss(&mut self, id: usize) {
+        *self.distances.entry(id).or_default() += MISSED;
+    }
+
+    /// Ranks the players by their points after the final round.
+    pub fn finish(&self, round: &RoundResult) -> GameResult {
+        let mut standings: Vec<Standing> = round
+            .players
+            .iter()
+            .map(|player| Standing {
+                id: player.id,
+                points: player.points,
+                distance: self.distances.get(&player.id).copied().unwrap_or(0.0),
+            })
+            .collect();
+
+        standings.sort_by(|a, b| {
+            b.points
+                .cmp(&a.points)
+                .then(a.distance.total_cmp(&b.distance))
+        });
+
+        GameResult {
+            standings,
+            best: self.best,
+        }
+    }
+}
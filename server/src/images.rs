@@ -1,42 +1,195 @@
-use std::io::Cursor;
+use std::{io::Cursor, sync::Arc};
 
-use crate::{error::Error, images::huggingface::Data};
+use crate::{
+    error::Error,
+    images::{bundle::Bundle, huggingface::Data, huggingface::HuggingFace, local::Local},
+};
 use bytes::Bytes;
-use image::{GenericImageView, ImageReader, imageops};
-use shared::image::{HEIGHT, WIDTH};
+use image::{GenericImageView, ImageReader, RgbImage, imageops};
+use shared::{
+    Direction, Display,
+    image::{HEIGHT, Pass, WIDTH},
+};
 
+pub mod bundle;
+pub mod cache;
 pub mod huggingface;
+pub mod local;
 
-pub async fn images() -> Result<([Bytes; 3], Data), Error> {
-    let random = rand::random_range(0..11054);
-    let data = huggingface::fetch(random).await?;
-    eprintln!("-> {}", data.image.src.clone());
-    let bytes = reqwest::get(data.image.src.clone()).await?.bytes().await?;
-    let bytes = bytes.to_vec();
-    eprintln!("-> fetched {} bytes of image data", bytes.len());
+/// The three slices of a panorama, each at full size and in full color.
+pub type Slices = [RgbImage; 3];
 
-    let img = ImageReader::new(Cursor::new(&bytes))
-        .with_guessed_format()?
-        .decode()?;
-    let img = img.as_rgb8().unwrap();
+/// Something which can supply panoramas along with where they were taken.
+pub trait ImageSource {
+    /// How many panoramas the source has.
+    fn len(&self) -> usize;
 
-    let (width, height) = img.dimensions();
-    let slice = width / 3;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-    let slices: [Bytes; 3] = std::array::from_fn(|i| {
-        let view = img.view(slice * i as u32, 0, slice, height).to_image();
-        let resized = imageops::resize(&view, WIDTH, HEIGHT, image::imageops::FilterType::Lanczos3);
-        let bytes = shared::image::encode(resized).unwrap();
+    /// Returns the panorama at `index`, already split up into its three resized slices.
+    fn fetch(&self, index: usize) -> impl Future<Output = Result<(Slices, Data), Error>> + Send;
+}
+
+/// The image source which the server was started with.
+#[derive(Debug)]
+pub enum Provider {
+    HuggingFace(HuggingFace),
+    Local(Local),
+    Bundle(Bundle),
+}
+
+impl ImageSource for Provider {
+    fn len(&self) -> usize {
+        match self {
+            Provider::HuggingFace(source) => source.len(),
+            Provider::Local(source) => source.len(),
+            Provider::Bundle(source) => source.len(),
+        }
+    }
+
+    async fn fetch(&self, index: usize) -> Result<(Slices, Data), Error> {
+        match self {
+            Provider::HuggingFace(source) => source.fetch(index).await,
+            Provider::Local(source) => source.fetch(index).await,
+            Provider::Bundle(source) => source.fetch(index).await,
+        }
+    }
+}
+
+/// Decodes a full panorama, and then cuts it into three resized slices. This takes a while,
+/// so it's done on a blocking thread rather than holding up everything else on the runtime.
+pub async fn slice(bytes: impl AsRef<[u8]> + Send + 'static) -> Result<Slices, Error> {
+    tokio::task::spawn_blocking(move || {
+        let bytes = bytes.as_ref();
+        eprintln!("-> fetched {} bytes of image data", bytes.len());
+
+        let img = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()?
+            .decode()?;
+        let img = img.into_rgb8();
+
+        let (width, height) = img.dimensions();
+        let slice = width / 3;
+
+        Ok(std::array::from_fn(|i| {
+            let view = img.view(slice * i as u32, 0, slice, height).to_image();
+            imageops::resize(&view, WIDTH, HEIGHT, image::imageops::FilterType::Lanczos3)
+        }))
+    })
+    .await?
+}
+
+/// The panorama of the current round, along with every way it's been encoded so far.
+///
+/// Clients get images in their own [`Format`], so each slice is only encoded
+/// the first time that someone with a new kind of display needs it.
+#[derive(Default)]
+pub struct Panorama {
+    slices: Arc<Slices>,
+    encoded: Vec<(Format, Direction, Encoded)>,
+}
 
-        bytes
+/// A slice of the panorama, encoded for one kind of client.
+#[derive(Clone, Debug)]
+pub struct Encoded {
+    pub width: u16,
+    pub height: u16,
+    pub image: Bytes,
+
+    /// The rest of the image after `image` for progressive clients, which is all of the pixels.
+    pub passes: Vec<Pass>,
+}
+
+/// Everything about how a client wants its images, which they're encoded for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Format {
+    pub display: Display,
+
+    /// Whether the client can decompress run-length encoded images.
+    pub rle: bool,
+
+    /// Whether the client wants images a pass at a time.
+    pub progressive: bool,
+}
+
+impl Panorama {
+    pub fn new(slices: Slices) -> Self {
+        Self {
+            slices: Arc::new(slices),
+            encoded: Vec::new(),
+        }
+    }
+
+    /// A slice of the panorama, fitted to a client's display and compressed if it can take it,
+    /// or [`None`] if it couldn't be encoded. Encoding runs on a blocking thread, since resizing
+    /// and quantizing are slow enough to hold up every other room otherwise.
+    pub async fn image(&mut self, format: Format, direction: Direction) -> Option<Encoded> {
+        let found = self
+            .encoded
+            .iter()
+            .find(|(x, y, _)| *x == format && *y == direction);
+        if let Some((.., encoded)) = found {
+            return Some(encoded.clone());
+        }
+
+        let slices = self.slices.clone();
+        let encoded =
+            tokio::task::spawn_blocking(move || encode(&slices[direction as usize], format)).await;
+        let encoded = match encoded {
+            Ok(encoded) => encoded?,
+            Err(error) => {
+                eprintln!("-> encoding {direction:?} slice for {format:?} failed: {error}");
+                return None;
+            }
+        };
+
+        eprintln!(
+            "-> encoded {direction:?} slice for {format:?} at {} bytes",
+            encoded.image.len() + encoded.passes.iter().map(|x| x.rows.len()).sum::<usize>()
+        );
+        self.encoded.push((format, direction, encoded.clone()));
+        Some(encoded)
+    }
+}
+
+/// Fits a slice to a client's display, and compresses it if the client can take it.
+fn encode(slice: &RgbImage, format: Format) -> Option<Encoded> {
+    let Format {
+        display,
+        rle,
+        progressive,
+    } = format;
+    let (width, height) = display.dimensions();
+    let fitted = shared::image::fit(slice, &display).and_then(|image| match progressive {
+        true => shared::image::interlace(&image, width, height, display.depth),
+        false => Ok((image, Vec::new())),
     });
+    let (mut image, mut passes) = match fitted {
+        Ok(fitted) => fitted,
+        Err(error) => {
+            eprintln!("-> couldn't encode slice for {format:?}: {error}");
+            return None;
+        }
+    };
+    if rle {
+        image = shared::image::compress(&image, display.depth);
+        for pass in &mut passes {
+            pass.rows = shared::image::compress(&pass.rows, display.depth);
+        }
+    }
 
-    eprintln!(
-        "-> compressed images at {}, {}, {} bytes",
-        slices[0].len(),
-        slices[1].len(),
-        slices[2].len()
-    );
+    // Fitting never makes an image any bigger than a full size one, which fits in a `u16`.
+    Some(Encoded {
+        width: width as u16,
+        height: height as u16,
+        image,
+        passes,
+    })
+}
 
-    Ok((slices, data))
+pub async fn images(source: &impl ImageSource) -> Result<(Slices, Data), Error> {
+    let random = rand::random_range(0..source.len());
+    source.fetch(random).await
 }
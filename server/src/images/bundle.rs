@@ -0,0 +1,240 @@
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+};
+
+use shared::{
+    Coordinate, Depth,
+    image::{HEIGHT, SIZE, WIDTH},
+};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader, BufWriter},
+};
+
+use crate::{
+    error::Error,
+    images::{
+        ImageSource, Slices,
+        huggingface::{Data, Image},
+        local::Local,
+    },
+};
+
+/// The magic bytes at the start of every bundle.
+pub const MAGIC: [u8; 4] = *b"GEOB";
+
+/// The current version of the bundle format.
+pub const VERSION: u8 = 2;
+
+/// The size of the fixed header, being the magic, version, count and index offset.
+pub const HEADER: u64 = 4 + 1 + 4 + 8;
+
+/// How the slices in a bundle are encoded, which was [`Depth::Rgb332`] before version 2.
+fn depth(version: u8) -> Option<Depth> {
+    match version {
+        1 => Some(Depth::Rgb332),
+        2 => Some(Depth::Rgb888),
+        _ => None,
+    }
+}
+
+/// The size of a single entry's image data, which is always three encoded slices.
+fn entry(depth: Depth) -> u64 {
+    3 * (SIZE * depth.bytes()) as u64
+}
+
+/// A single dataset entry in the index of a bundle.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub offset: u64,
+    pub coordinates: Coordinate,
+    pub country: [char; 2],
+    pub address: String,
+}
+
+/// A single file dataset, usable without an internet connection.
+///
+/// Everything is big endian, just like the protocol. The file starts with a header,
+/// which is [`MAGIC`], the [`VERSION`], the entry count as a `u32` and the offset of the index
+/// as a `u64`. Then comes the image data of every entry, which is three slices in RGB888,
+/// and finally the index. Each index entry has its image offset as a `u64`, the
+/// latitude & longitude as `f32`, the two character country code and then the address
+/// prefixed by its length as a `u16`.
+#[derive(Debug)]
+pub struct Bundle {
+    pub path: PathBuf,
+    pub entries: Vec<Entry>,
+
+    /// How the slices are encoded, depending on the version of the bundle.
+    pub depth: Depth,
+}
+
+async fn country(reader: &mut BufReader<File>) -> Result<[char; 2], Error> {
+    let mut country = [0u8; 2];
+    reader.read_exact(&mut country).await?;
+    Ok(country.map(char::from))
+}
+
+impl Bundle {
+    /// Opens a bundle, reading the index and validating it against the rest of the file.
+    pub async fn open(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path).await?;
+        let size = file.metadata().await?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).await?;
+        if magic != MAGIC {
+            return Err(Error::Bundle("bad magic bytes"));
+        }
+
+        let Some(depth) = depth(reader.read_u8().await?) else {
+            return Err(Error::Bundle("unsupported version"));
+        };
+
+        let count = reader.read_u32().await? as u64;
+        let index = reader.read_u64().await?;
+        if index > size || index < HEADER + count * entry(depth) {
+            return Err(Error::Bundle("index offset out of bounds"));
+        }
+
+        reader.seek(SeekFrom::Start(index)).await?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let offset = reader.read_u64().await?;
+            let latitude = reader.read_f32().await?;
+            let longitude = reader.read_f32().await?;
+            let country = country(&mut reader).await?;
+
+            let mut address = vec![0u8; reader.read_u16().await? as usize];
+            reader.read_exact(&mut address).await?;
+            let address = std::str::from_utf8(&address)?.to_owned();
+
+            if offset < HEADER || offset + entry(depth) > index {
+                return Err(Error::Bundle("entry offset out of bounds"));
+            }
+
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return Err(Error::Bundle("entry coordinates out of range"));
+            }
+
+            entries.push(Entry {
+                offset,
+                coordinates: Coordinate {
+                    latitude,
+                    longitude,
+                },
+                country,
+                address,
+            });
+        }
+
+        if reader.stream_position().await? != size {
+            return Err(Error::Bundle("trailing data after index"));
+        }
+
+        if entries.is_empty() {
+            return Err(Error::NoImages(path.to_owned()));
+        }
+
+        eprintln!(
+            "server: loaded bundle {} with {} images",
+            path.display(),
+            entries.len()
+        );
+
+        Ok(Self {
+            path: path.to_owned(),
+            entries,
+            depth,
+        })
+    }
+
+    /// Builds a bundle from a directory of images with sidecar JSON metadata,
+    /// skipping over any images which can't be read.
+    pub async fn build(directory: &Path, output: &Path) -> Result<usize, Error> {
+        let local = Local::new(directory)?;
+        let mut writer = BufWriter::new(File::create(output).await?);
+
+        writer.write_all(&MAGIC).await?;
+        writer.write_u8(VERSION).await?;
+        writer.write_u32(0).await?;
+        writer.write_u64(0).await?;
+
+        let mut entries = Vec::new();
+        let mut offset = HEADER;
+        for index in 0..local.len() {
+            let (slices, data) = match local.fetch(index).await {
+                Ok(fetched) => fetched,
+                Err(error) => {
+                    eprintln!("-> skipping {}: {error}", local.entries[index].display());
+                    continue;
+                }
+            };
+
+            for slice in &slices {
+                writer.write_all(slice.as_raw()).await?;
+            }
+
+            entries.push(Entry {
+                offset,
+                coordinates: data.coordinates,
+                country: data.country,
+                address: data.address,
+            });
+            offset += entry(Depth::Rgb888);
+        }
+
+        for entry in &entries {
+            writer.write_u64(entry.offset).await?;
+            writer.write_f32(entry.coordinates.latitude).await?;
+            writer.write_f32(entry.coordinates.longitude).await?;
+            writer.write_all(&entry.country.map(|x| x as u8)).await?;
+
+            // Cut short on a character boundary, so that it's still valid UTF-8.
+            let address = &entry.address[..entry.address.floor_char_boundary(u16::MAX as usize)];
+            writer.write_u16(address.len() as u16).await?;
+            writer.write_all(address.as_bytes()).await?;
+        }
+
+        writer.seek(SeekFrom::Start(5)).await?;
+        writer.write_u32(entries.len() as u32).await?;
+        writer.write_u64(offset).await?;
+        writer.flush().await?;
+
+        Ok(entries.len())
+    }
+}
+
+impl ImageSource for Bundle {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    async fn fetch(&self, index: usize) -> Result<(Slices, Data), Error> {
+        let entry = &self.entries[index];
+        let mut file = File::open(&self.path).await?;
+        file.seek(SeekFrom::Start(entry.offset)).await?;
+
+        let mut slices = Slices::default();
+        for slice in &mut slices {
+            let mut buf = vec![0u8; (SIZE * self.depth.bytes()) as usize];
+            file.read_exact(&mut buf).await?;
+            *slice = shared::image::decode(&buf, WIDTH, HEIGHT, self.depth)?;
+        }
+
+        Ok((
+            slices,
+            Data {
+                image: Image {
+                    src: format!("{}#{index}", self.path.display()),
+                    ..Default::default()
+                },
+                coordinates: entry.coordinates,
+                country: entry.country,
+                address: entry.address.clone(),
+            },
+        ))
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use image::RgbImage;
+use shared::image::{HEIGHT, SIZE, WIDTH};
+
+use crate::{
+    error::Error,
+    images::{
+        Slices,
+        huggingface::{Data, Image},
+        local::Local,
+    },
+};
+
+/// How many bytes a single slice takes up, in RGB888.
+const SLICE: usize = SIZE as usize * 3;
+
+/// An on-disk cache of already processed slices, keyed by dataset index.
+///
+/// Each entry is stored as `{index}.bin`, containing the three slices' pixels back to back,
+/// and `{index}.json`, which holds the metadata in the same flat format as local sidecars.
+#[derive(Debug)]
+pub struct Cache {
+    pub directory: PathBuf,
+}
+
+impl Cache {
+    pub fn new(directory: PathBuf) -> Result<Self, Error> {
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        self.directory.join(format!("{index}.bin"))
+    }
+
+    /// Loads a cached entry, returning [`None`] if it's missing or unreadable.
+    pub async fn load(&self, index: usize) -> Option<(Slices, Data)> {
+        let path = self.path(index);
+        let bytes = tokio::fs::read(&path).await.ok()?;
+
+        // Entries from before slices were kept in full color are the wrong size, and get refetched.
+        if bytes.len() != 3 * SLICE {
+            return None;
+        }
+
+        let data = Local::metadata(&path).await.ok()?;
+        let slices = std::array::from_fn(|i| {
+            let pixels = bytes[i * SLICE..(i + 1) * SLICE].to_vec();
+            RgbImage::from_raw(WIDTH, HEIGHT, pixels).expect("slice is the right size")
+        });
+
+        Some((
+            slices,
+            Data {
+                image: Image {
+                    src: path.display().to_string(),
+                    ..Default::default()
+                },
+                ..data
+            },
+        ))
+    }
+
+    pub async fn store(&self, index: usize, slices: &Slices, data: &Data) -> Result<(), Error> {
+        let path = self.path(index);
+        let metadata = json::object! {
+            latitude: data.coordinates.latitude,
+            longitude: data.coordinates.longitude,
+            country_iso_alpha2: data.country.iter().collect::<String>(),
+            address: data.address.clone(),
+        };
+
+        // The metadata goes first, so that a partial entry never has any image data.
+        tokio::fs::write(path.with_extension("json"), metadata.dump()).await?;
+        let pixels: Vec<u8> = slices.iter().flat_map(|x| x.as_raw()).copied().collect();
+        tokio::fs::write(&path, pixels).await?;
+
+        Ok(())
+    }
+}
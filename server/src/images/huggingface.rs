@@ -1,7 +1,13 @@
 use json::JsonValue;
 use shared::Coordinate;
 
-use crate::error::Error;
+use crate::{
+    error::Error,
+    images::{ImageSource, Slices, cache::Cache, slice},
+};
+
+/// Total amount of rows in the dataset.
+pub const ROWS: usize = 11054;
 
 #[derive(Debug, Default)]
 pub struct Image {
@@ -18,21 +24,19 @@ pub struct Data {
     pub address: String,
 }
 
-pub fn parse(json: JsonValue) -> Option<Data> {
-    let row = &json["rows"][0]["row"];
-
-    let image = &row["image"];
-    let src = image["src"].as_str()?;
-    let height = image["height"].as_u16()?;
-    let width = image["width"].as_u16()?;
-    let image = Image {
-        src: src.to_owned(),
-        height,
-        width,
+/// Parses the location metadata of a single dataset row, leaving the image empty.
+///
+/// Latitude and longitude may either be strings, like in the dataset, or plain numbers.
+pub fn parse_row(row: &JsonValue) -> Option<Data> {
+    let coordinate = |value: &JsonValue| -> Option<f32> {
+        match value.as_str() {
+            Some(string) => string.parse().ok(),
+            None => value.as_f32(),
+        }
     };
 
-    let latitude: f32 = row["latitude"].as_str()?.parse().ok()?;
-    let longitude: f32 = row["longitude"].as_str()?.parse().ok()?;
+    let latitude = coordinate(&row["latitude"])?;
+    let longitude = coordinate(&row["longitude"])?;
     let address: String = row["address"].as_str()?.to_owned();
     let country: [char; 2] = row["country_iso_alpha2"]
         .as_str()?
@@ -43,7 +47,7 @@ pub fn parse(json: JsonValue) -> Option<Data> {
         .ok()?;
 
     Some(Data {
-        image,
+        image: Image::default(),
         coordinates: Coordinate {
             latitude,
             longitude,
@@ -53,6 +57,25 @@ pub fn parse(json: JsonValue) -> Option<Data> {
     })
 }
 
+pub fn parse(json: JsonValue) -> Option<Data> {
+    let row = &json["rows"][0]["row"];
+
+    let image = &row["image"];
+    let src = image["src"].as_str()?;
+    let height = image["height"].as_u16()?;
+    let width = image["width"].as_u16()?;
+    let image = Image {
+        src: src.to_owned(),
+        height,
+        width,
+    };
+
+    Some(Data {
+        image,
+        ..parse_row(row)?
+    })
+}
+
 pub async fn fetch(index: usize) -> Result<Data, Error> {
     let url = format!(
         "https://datasets-server.huggingface.co/rows?dataset=yunusserhat%2Frandom_streetview_images&config=default&split=train&offset={index}&length=1"
@@ -62,5 +85,41 @@ pub async fn fetch(index: usize) -> Result<Data, Error> {
     let string = std::str::from_utf8(&bytes)?;
     let json = json::parse(string)?;
 
-    Ok(parse(json).unwrap())
+    parse(json).ok_or(Error::Metadata)
+}
+
+/// Pulls random rows from the Hugging Face street view dataset.
+#[derive(Debug, Default)]
+pub struct HuggingFace {
+    pub cache: Option<Cache>,
+}
+
+impl ImageSource for HuggingFace {
+    fn len(&self) -> usize {
+        ROWS
+    }
+
+    async fn fetch(&self, index: usize) -> Result<(Slices, Data), Error> {
+        if let Some(cached) = self.cache.as_ref()
+            && let Some(cached) = cached.load(index).await
+        {
+            eprintln!("-> cached row {index}");
+            return Ok(cached);
+        }
+
+        let data = fetch(index).await?;
+        eprintln!("-> {}", data.image.src);
+
+        let bytes = reqwest::get(data.image.src.clone()).await?.bytes().await?;
+        let slices = slice(bytes).await?;
+
+        // The image is fine either way, it just has to be fetched again next time.
+        if let Some(cache) = &self.cache
+            && let Err(error) = cache.store(index, &slices, &data).await
+        {
+            eprintln!("-> couldn't cache row {index}: {error}");
+        }
+
+        Ok((slices, data))
+    }
 }
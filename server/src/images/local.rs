@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::Error,
+    images::{
+        ImageSource, Slices,
+        huggingface::{self, Data, Image},
+        slice,
+    },
+};
+
+const EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "bmp"];
+
+/// Reads images from a local directory, where every image has a
+/// sidecar JSON file with the same name containing its location.
+///
+/// The sidecar can either be a Hugging Face rows response like `misc/demo.json`,
+/// or a flat object with `latitude`, `longitude`, `country_iso_alpha2` and `address`.
+#[derive(Debug)]
+pub struct Local {
+    pub entries: Vec<PathBuf>,
+}
+
+impl Local {
+    pub fn new(directory: &Path) -> Result<Self, Error> {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .and_then(|x| x.to_str())
+                    .is_some_and(|x| EXTENSIONS.contains(&x.to_lowercase().as_str()))
+            })
+            .filter(|path| path.with_extension("json").exists())
+            .collect();
+
+        if entries.is_empty() {
+            return Err(Error::NoImages(directory.to_owned()));
+        }
+
+        entries.sort();
+        eprintln!(
+            "server: found {} images in {}",
+            entries.len(),
+            directory.display()
+        );
+
+        Ok(Self { entries })
+    }
+
+    pub async fn metadata(path: &Path) -> Result<Data, Error> {
+        let string = tokio::fs::read_to_string(path.with_extension("json")).await?;
+        let json = json::parse(&string)?;
+
+        if json["rows"].is_array() {
+            huggingface::parse_row(&json["rows"][0]["row"])
+        } else {
+            huggingface::parse_row(&json)
+        }
+        .ok_or(Error::Metadata)
+    }
+}
+
+impl ImageSource for Local {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    async fn fetch(&self, index: usize) -> Result<(Slices, Data), Error> {
+        let path = &self.entries[index];
+        eprintln!("-> {}", path.display());
+
+        let data = Self::metadata(path).await?;
+        let bytes = tokio::fs::read(path).await?;
+
+        Ok((
+            slice(bytes).await?,
+            Data {
+                image: Image {
+                    src: path.display().to_string(),
+                    ..Default::default()
+                },
+                ..data
+            },
+        ))
+    }
+}
@@ -0,0 +1,34 @@
+//! The game server, which is a library as well so that other crates can test against the real one.
+
+use std::net::SocketAddr;
+
+use shared::Packet;
+use tokio::{net::TcpStream, time::Instant};
+
+use crate::error::Error;
+
+pub mod client;
+pub mod error;
+pub mod game;
+pub mod images;
+pub mod lobby;
+pub mod prefetch;
+pub mod room;
+pub mod round;
+pub mod server;
+
+#[cfg(test)]
+mod tests;
+
+pub enum Message {
+    Connection(TcpStream, SocketAddr),
+    Packet(usize, Result<Packet, shared::Error>),
+    /// Everyone in the room with this code has guessed.
+    GuessingComplete(String),
+    Deadline(Instant),
+    /// The grace period for resuming the session with this token is over.
+    Expired(u64),
+    /// The room with this code can send the next passes of its progressive images.
+    Rows(String),
+    Quit,
+}
@@ -1,43 +1,56 @@
-use shared::Packet;
+use shared::{Capabilities, Packet};
 
-use crate::{Message, Server, error::Error, round};
+use crate::{Message, error::Error, room::Room, round};
 
-pub async fn handler(server: &mut Server, message: Message) -> Result<(), Error> {
+pub async fn handler(room: &mut Room, message: Message) -> Result<(), Error> {
     match message {
         Message::Packet(id, packet) => match packet {
-            Ok(Packet::Init { options }) => {
-                server[id].options = Some(options.clone());
-                eprintln!("server(client {id}): {options:?}");
+            Ok(Packet::Init {
+                capabilities,
+                options,
+                display,
+            }) => {
+                room[id].options = Some(options.clone());
+                room[id].capabilities = capabilities;
+                room[id].display = display;
+                eprintln!("server(client {id}): {options:?}, {capabilities:?}, {display:?}");
 
-                let lobby = server.lobby().await;
-                let client = &mut server[id];
-                client
-                    .write(Packet::Confirmed { id, options, lobby })
-                    .await?;
+                let lobby = room.lobby().await;
+                let token = room[id].token;
+                let packet = Packet::Confirmed {
+                    // Only what the client asked for is turned on, so that's all it's told about.
+                    capabilities: Capabilities::ALL & capabilities,
+                    id,
+                    token,
+                    options,
+                    lobby,
+                };
+                room.send(id, packet).await?;
+                if !room.contains(id) {
+                    return Ok(());
+                }
 
-                server
-                    .broadcast_lobby(id, shared::lobby::Action::Join)
-                    .await;
+                room.broadcast_lobby(id, shared::lobby::Action::Join).await;
             }
             Ok(Packet::WaitingStatus { ready }) => {
-                server[id].ready = ready;
+                room[id].ready = ready;
                 eprintln!("server(client {id}): ready = {ready}");
-                server
-                    .broadcast_lobby(id, shared::lobby::Action::Ready)
-                    .await;
+                room.broadcast_lobby(id, shared::lobby::Action::Ready).await;
 
-                if server.ready() {
-                    server.state = round::new(server, None).await?;
+                if room.ready() {
+                    room.state = round::new(room, id, None).await?;
                 }
             }
-            Ok(Packet::SoftQuit) => server.soft_kick(id).await?,
-            Ok(other) => server.kick(id, shared::Error::Illegal(other)).await?,
-            Err(error) => server.kick(id, error).await?,
+            Ok(Packet::SoftQuit) => room.soft_kick(id).await?,
+            Ok(other) => room.kick(id, shared::Error::Illegal(other)).await?,
+            Err(error) => room.kick(id, error).await?,
         },
-        Message::Connection(socket, address) => {
-            server.client(socket, address).await?;
-        }
-        Message::Quit | Message::GuessingComplete => return Ok(()),
+        Message::Connection(..)
+        | Message::Quit
+        | Message::GuessingComplete(_)
+        | Message::Deadline(_)
+        | Message::Expired(_)
+        | Message::Rows(_) => (),
     }
 
     Ok(())
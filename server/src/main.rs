@@ -1,34 +1,95 @@
-use std::net::SocketAddr;
+use std::{path::PathBuf, time::Duration};
 
+use clap::{Parser, Subcommand};
 use futures::executor::block_on;
-use shared::Packet;
-use tokio::net::TcpStream;
-
-use crate::{error::Error, server::Server};
-
-pub mod client;
-pub mod error;
-pub mod images;
-pub mod lobby;
-pub mod round;
-pub mod server;
-
-pub enum Message {
-    Connection(TcpStream, SocketAddr),
-    Packet(usize, Result<Packet, shared::Error>),
-    GuessingComplete,
-    Quit,
+
+use server::{
+    Message,
+    images::{Provider, bundle::Bundle, cache::Cache, huggingface::HuggingFace, local::Local},
+    prefetch::Prefetch,
+    server::{Config, Server},
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Directory of images with sidecar JSON metadata, instead of Hugging Face.
+    #[arg(short, long, conflicts_with = "bundle")]
+    images: Option<PathBuf>,
+
+    /// Offline dataset bundle to play with, instead of Hugging Face.
+    #[arg(short, long)]
+    bundle: Option<PathBuf>,
+
+    /// Directory to cache processed Hugging Face images in.
+    #[arg(short, long, conflicts_with_all = ["images", "bundle"])]
+    cache: Option<PathBuf>,
+
+    /// Time limit for each round in seconds, after which everyone who hasn't guessed gets zero.
+    #[arg(short, long)]
+    time_limit: Option<u64>,
+
+    /// How many rounds are in a game, which go on forever if this isn't set.
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    rounds: Option<u64>,
+
+    /// How many seconds a client which dropped mid-game has to resume its session.
+    #[arg(short, long, default_value_t = 60)]
+    grace: u64,
+
+    /// How many rounds to keep ready in the background.
+    #[arg(short, long, default_value_t = 2, value_parser = clap::value_parser!(u16).range(1..))]
+    prefetch: u16,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Builds an offline dataset bundle from a directory of images and JSON metadata.
+    Bundle {
+        /// Directory of images with sidecar JSON metadata.
+        directory: PathBuf,
+
+        /// Where to write the bundle.
+        output: PathBuf,
+    },
 }
 
 #[tokio::main]
 pub async fn main() -> eyre::Result<()> {
-    let mut server = Server::new().await?;
+    let args = Args::parse();
+    if let Some(Command::Bundle { directory, output }) = args.command {
+        let count = Bundle::build(&directory, &output).await?;
+        eprintln!("server: wrote {count} images to {}", output.display());
+        return Ok(());
+    }
+
+    let images = match (args.images, args.bundle) {
+        (Some(directory), _) => Provider::Local(Local::new(&directory)?),
+        (None, Some(bundle)) => Provider::Bundle(Bundle::open(&bundle).await?),
+        (None, None) => Provider::HuggingFace(HuggingFace {
+            cache: args.cache.map(Cache::new).transpose()?,
+        }),
+    };
+
+    let prefetch = Prefetch::new(images, args.prefetch as usize);
+    let config = Config {
+        time_limit: args.time_limit.map(Duration::from_secs),
+        rounds: args.rounds.map(|x| x as usize),
+        grace: Duration::from_secs(args.grace),
+    };
+
+    let mut server = Server::new(prefetch, config).await?;
     let tx = server.tx.clone();
     ctrlc::set_handler(move || block_on(tx.send(Message::Quit)).unwrap()).unwrap();
 
     server.run().await?;
-    for client in server.clients.drain(..) {
-        client.close().await;
+    for room in server.rooms.drain(..) {
+        for client in room.clients {
+            client.close().await;
+        }
     }
 
     Ok(())
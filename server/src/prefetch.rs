@@ -0,0 +1,77 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::mpsc, task::JoinHandle, time::sleep};
+
+use crate::{
+    error::Error,
+    images::{Provider, Slices, huggingface::Data, images},
+};
+
+pub type Fetched = Result<(Slices, Data), Error>;
+
+/// How long to wait before retrying after the first failed fetch, which doubles with every
+/// failure after that.
+const BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest wait between retries, so that the worker picks back up soon after the network does.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long starting a round waits on an image when none are ready yet. Everything else on the
+/// server waits along with it, so this shouldn't be much longer than a fetch usually takes.
+const PATIENCE: Duration = Duration::from_secs(20);
+
+/// Keeps the next few rounds ready in the background,
+/// so that starting a round doesn't need to wait on the network.
+pub struct Prefetch {
+    rx: mpsc::Receiver<(Slices, Data)>,
+    handle: JoinHandle<()>,
+}
+
+impl Prefetch {
+    /// Keeps fetching images, retrying with backoff when a fetch fails, so that only images which
+    /// were actually fetched end up waiting for a round.
+    async fn worker(source: Arc<Provider>, tx: mpsc::Sender<(Slices, Data)>) {
+        let mut backoff = BACKOFF;
+        loop {
+            let fetched = match images(source.as_ref()).await {
+                Ok(fetched) => fetched,
+                Err(error) => {
+                    eprintln!(
+                        "server(prefetch): failed to fetch image, retrying in {}s: {error}",
+                        backoff.as_secs()
+                    );
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            };
+
+            backoff = BACKOFF;
+            if tx.send(fetched).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Starts prefetching, keeping up to `rounds` images ready at once.
+    pub fn new(source: Provider, rounds: usize) -> Self {
+        let (tx, rx) = mpsc::channel(rounds.max(1));
+        let handle = tokio::spawn(Self::worker(Arc::new(source), tx));
+
+        Self { rx, handle }
+    }
+
+    /// Takes the next prefetched image, waiting a little while for it if none are ready yet.
+    pub async fn next(&mut self) -> Fetched {
+        match tokio::time::timeout(PATIENCE, self.rx.recv()).await {
+            Ok(fetched) => Ok(fetched.expect("prefetch worker stopped unexpectedly")),
+            Err(_) => Err(Error::Unavailable),
+        }
+    }
+}
+
+impl Drop for Prefetch {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
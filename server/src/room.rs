@@ -0,0 +1,643 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::{Index, IndexMut},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures::future::join_all;
+use shared::{Capabilities, Direction, PROTOCOL_VERSION, Packet, RoundResult, image::Pass};
+use tokio::{
+    sync::{Mutex, mpsc},
+    time::{Instant, sleep},
+};
+
+use crate::{
+    Message,
+    client::{Client, Session},
+    error::Error,
+    game::Game,
+    images::{Encoded, Panorama},
+    lobby,
+    prefetch::Prefetch,
+    round,
+    server::{Config, State},
+};
+
+/// Characters which room codes are made of, leaving out ones that are easy to mix up.
+const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// How long to wait between the passes of a progressive image. Without a pause, every pass would
+/// be sitting in the socket's buffers long before the player got to guess from the first few.
+const PASS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Generates a random room code, which is always [`shared::ROOM_CODE_LEN`] long.
+pub fn code() -> String {
+    (0..shared::ROOM_CODE_LEN)
+        .map(|_| ALPHABET[rand::random_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
+/// The passes of a progressive image, as the packets which they're sent in.
+fn rows(direction: Direction, passes: Vec<Pass>) -> VecDeque<Packet> {
+    passes
+        .into_iter()
+        .map(|Pass { first, step, rows }| Packet::Rows {
+            direction,
+            first,
+            step,
+            image: rows,
+        })
+        .collect()
+}
+
+/// A single group of players, with its own game going on.
+pub struct Room {
+    pub code: String,
+    pub clients: Vec<Client>,
+
+    /// Clients which disconnected mid-game, and can still resume.
+    pub sessions: Vec<Session>,
+    pub state: State,
+    pub panorama: Panorama,
+
+    /// When the current round will end, if it has a time limit.
+    pub deadline: Option<Instant>,
+    pub game: Game,
+    pub config: Arc<Config>,
+    pub prefetch: Arc<Mutex<Prefetch>>,
+    pub tx: mpsc::Sender<Message>,
+
+    /// Whether there's a [`Message::Rows`] on its way, so that there's only ever one.
+    scheduled: bool,
+
+    /// [`Packet::Init`]s from clients which showed up mid-game, to handle once the game is over.
+    parked: Vec<(usize, Packet)>,
+}
+
+impl IndexMut<usize> for Room {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.clients
+            .iter_mut()
+            .find(|x| x.id == index)
+            .unwrap_or_else(|| panic!("couldn't find client {index}"))
+    }
+}
+
+impl Index<usize> for Room {
+    type Output = Client;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.clients
+            .iter()
+            .find(|x| x.id == index)
+            .unwrap_or_else(|| panic!("couldn't find client {index}"))
+    }
+}
+
+impl Room {
+    pub fn new(
+        code: String,
+        config: Arc<Config>,
+        prefetch: Arc<Mutex<Prefetch>>,
+        tx: mpsc::Sender<Message>,
+    ) -> Self {
+        Self {
+            code,
+            clients: Vec::new(),
+            sessions: Vec::new(),
+            state: State::Lobby,
+            panorama: Panorama::default(),
+            deadline: None,
+            game: Game::default(),
+            config,
+            prefetch,
+            tx,
+            scheduled: false,
+            parked: Vec::new(),
+        }
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.clients.iter().any(|x| x.id == id)
+    }
+
+    pub async fn return_to_lobby(&mut self, id: usize) {
+        // There's no game left to resume, or images to finish.
+        self.sessions.clear();
+        self.clear_rows();
+        self.state = State::Lobby;
+        self.broadcast_lobby(id, shared::lobby::Action::Return)
+            .await;
+    }
+
+    /// Sends everyone the final standings, and then returns to the lobby.
+    pub async fn game_over(&mut self, id: usize, round: &RoundResult) {
+        eprintln!(
+            "server(room {}): game over after {} rounds",
+            self.code,
+            round.number + 1
+        );
+        let results = self.game.finish(round);
+        self.broadcast(&Packet::GameOver { results }, None).await;
+
+        self.clients.iter_mut().for_each(|x| x.ready = false);
+        self.return_to_lobby(id).await;
+    }
+
+    pub async fn verify(&mut self, id: usize) {
+        let players = self.clients.iter().filter(|x| x.initialized()).count();
+        if players + self.sessions.len() < 2 {
+            eprintln!("server: not enough players, returning to lobby...");
+            self.return_to_lobby(id).await;
+        }
+    }
+
+    pub async fn soft_kick(&mut self, client: usize) -> Result<(), Error> {
+        eprintln!("server(client {client}): soft quit");
+        self[client].options = None;
+        self[client].ready = false;
+        self.verify(client).await;
+
+        if self.state == State::Lobby && self.ready() {
+            self.state = round::new(self, client, None).await?;
+        } else {
+            self.verify(client).await;
+        }
+
+        Ok(())
+    }
+
+    /// Takes a client out of the room, letting everyone else know that they left.
+    pub async fn leave(&mut self, client: usize) -> Result<Option<Client>, Error> {
+        let removed = self
+            .clients
+            .iter()
+            .position(|x| x.id == client)
+            .map(|index| self.clients.remove(index));
+        self.take_parked(client);
+
+        if self.state == State::Lobby {
+            self.broadcast_lobby(client, shared::lobby::Action::Leave)
+                .await;
+
+            if self.ready() {
+                self.state = round::new(self, client, None).await?;
+            }
+        } else {
+            self.verify(client).await;
+            self.check_guesses().await?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Keeps a client's place in the game after its connection dropped, so that it can resume.
+    pub async fn disconnect(&mut self, client: usize, error: shared::Error) -> Result<(), Error> {
+        eprintln!("server(client {client}): disconnected: {error}");
+        let Some(index) = self.clients.iter().position(|x| x.id == client) else {
+            return Ok(());
+        };
+        self.take_parked(client);
+
+        if let Some(session) = self.clients.remove(index).session().await {
+            let tx = self.tx.clone();
+            let (token, grace) = (session.token, self.config.grace);
+            tokio::spawn(async move {
+                sleep(grace).await;
+                let _ = tx.send(Message::Expired(token)).await;
+            });
+
+            self.sessions.push(session);
+        }
+
+        self.verify(client).await;
+        match &self.state {
+            State::Round(_) => self.check_guesses().await?,
+            State::Results(round) if self.ready() => {
+                let round = round.clone();
+                self.advance(client, &round).await?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Gives up on a disconnected client, since its grace period is over.
+    pub async fn expire(&mut self, token: u64) {
+        let Some(index) = self.sessions.iter().position(|x| x.token == token) else {
+            return;
+        };
+
+        let session = self.sessions.remove(index);
+        eprintln!("server(client {}): session expired", session.id);
+        self.verify(session.id).await;
+    }
+
+    /// Sends a single client a packet. If that fails, the client is dropped like it would be
+    /// for failing to read from it, since one broken socket shouldn't take the server down.
+    pub async fn send(&mut self, id: usize, packet: Packet) -> Result<(), Error> {
+        let Err(error) = self[id].write(packet).await else {
+            return Ok(());
+        };
+
+        match self.state {
+            State::Lobby => self.kick(id, error).await,
+            State::Round(_) | State::Results(_) => self.disconnect(id, error).await,
+        }
+    }
+
+    /// Holds on to a packet from a client which isn't in the game that's going on. The only one
+    /// that makes sense is the [`Packet::Init`] which it joins the lobby with once the game is over.
+    async fn park(&mut self, id: usize, packet: Packet) -> Result<(), Error> {
+        if !matches!(packet, Packet::Init { .. }) || self.parked.iter().any(|(x, _)| *x == id) {
+            return self.kick(id, shared::Error::Illegal(packet)).await;
+        }
+
+        eprintln!("server(client {id}): waiting for the game to end");
+        self.parked.push((id, packet));
+        Ok(())
+    }
+
+    /// Takes back a client's parked [`Packet::Init`], for when it goes somewhere else instead.
+    pub fn take_parked(&mut self, id: usize) -> Option<Packet> {
+        let index = self.parked.iter().position(|(x, _)| *x == id)?;
+        Some(self.parked.remove(index).1)
+    }
+
+    /// Lets in the clients which were waiting on the game to end, once the room is back in its lobby.
+    pub async fn unpark(&mut self) -> Result<(), Error> {
+        while self.state == State::Lobby && !self.parked.is_empty() {
+            let (id, packet) = self.parked.remove(0);
+            if self.contains(id) {
+                lobby::handler(self, Message::Packet(id, Ok(packet))).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Catches a client which just resumed its session up with the game.
+    pub async fn resume(&mut self, id: usize) -> Result<(), Error> {
+        let Some(options) = self[id].options.clone() else {
+            return Ok(());
+        };
+
+        let lobby = self.lobby().await;
+        let packet = Packet::Confirmed {
+            capabilities: Capabilities::ALL & self[id].capabilities,
+            id,
+            token: self[id].token,
+            options,
+            lobby,
+        };
+        self.send(id, packet).await?;
+        if !self.contains(id) {
+            return Ok(());
+        }
+
+        let packet = match &self.state {
+            State::Lobby => {
+                self.broadcast_lobby(id, shared::lobby::Action::Join).await;
+                return Ok(());
+            }
+            State::Round(round) => {
+                let number = round.number;
+                let format = self[id].format();
+                let Some(Encoded {
+                    width,
+                    height,
+                    image,
+                    passes,
+                }) = self.panorama.image(format, Direction::Center).await
+                else {
+                    return Ok(());
+                };
+                self[id].rows = rows(Direction::Center, passes);
+
+                let left = self
+                    .deadline
+                    .map(|x| x.saturating_duration_since(Instant::now()));
+                Packet::Round {
+                    number,
+                    // Zero would mean that there's no time limit at all.
+                    deadline: left.map(|x| (x.as_secs() as u32).max(1)).unwrap_or(0),
+                    width,
+                    height,
+                    image,
+                }
+            }
+            State::Results(round) => Packet::Result {
+                results: round.clone(),
+            },
+        };
+
+        self.send(id, packet).await?;
+        self.schedule_rows();
+        Ok(())
+    }
+
+    /// Ends guessing once everyone who's still connected has guessed.
+    pub async fn check_guesses(&mut self) -> Result<(), Error> {
+        let State::Round(round) = &self.state else {
+            return Ok(());
+        };
+
+        let done = round
+            .players
+            .iter()
+            .filter(|x| self.contains(x.id))
+            .all(|x| x.guess.is_some());
+
+        if done {
+            self.tx
+                .send(Message::GuessingComplete(self.code.clone()))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts the next round once everyone is ready, or ends the game if that was the last one.
+    pub async fn advance(&mut self, id: usize, round: &RoundResult) -> Result<(), Error> {
+        if self
+            .config
+            .rounds
+            .is_some_and(|rounds| round.number + 1 >= rounds)
+        {
+            self.game_over(id, round).await;
+        } else {
+            eprintln!("server(room {}): all ready, starting new round", self.code);
+            self.state = round::new(self, id, Some(round)).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn kick(&mut self, client: usize, error: shared::Error) -> Result<(), Error> {
+        eprintln!("server(client {client}): removed: {error}");
+        if let Some(reason) = error.reason() {
+            let packet = Packet::Error {
+                reason,
+                version: PROTOCOL_VERSION,
+            };
+
+            // The client is on its way out regardless, so it not receiving this is fine.
+            let _ = self[client].write(packet).await;
+        }
+        self.leave(client).await?;
+
+        Ok(())
+    }
+
+    /// Sends everyone the start of a round, with the center slice fitted to each of their displays.
+    pub async fn broadcast_round(&mut self, number: usize, deadline: u32) {
+        // Everything's encoded up front, since each new format has to wait on its own encoding.
+        let mut packets = HashMap::new();
+        for client in self.clients.iter_mut().filter(|x| x.initialized()) {
+            let Some(Encoded {
+                width,
+                height,
+                image,
+                passes,
+            }) = self
+                .panorama
+                .image(client.format(), Direction::Center)
+                .await
+            else {
+                continue;
+            };
+            client.rows = rows(Direction::Center, passes);
+            let packet = Packet::Round {
+                number,
+                deadline,
+                width,
+                height,
+                image,
+            };
+            packets.insert(client.id, packet);
+        }
+
+        let futures = self
+            .clients
+            .iter_mut()
+            .filter_map(|client| Some(client.write(packets.remove(&client.id)?)));
+        join_all(futures).await;
+        self.schedule_rows();
+    }
+
+    /// Makes sure that a [`Message::Rows`] is on its way after [`PASS_INTERVAL`], if anyone is
+    /// still waiting on the rest of a progressive image. It goes through the server like
+    /// everything else, so that nothing else has to wait on the whole image.
+    fn schedule_rows(&mut self) {
+        if self.scheduled || self.clients.iter().all(|x| x.rows.is_empty()) {
+            return;
+        }
+
+        self.scheduled = true;
+        let (tx, code) = (self.tx.clone(), self.code.clone());
+        tokio::spawn(async move {
+            sleep(PASS_INTERVAL).await;
+            let _ = tx.send(Message::Rows(code)).await;
+        });
+    }
+
+    /// Sends everyone who's waiting on a progressive image its next pass.
+    pub async fn send_rows(&mut self) {
+        self.scheduled = false;
+        let futures = self.clients.iter_mut().filter_map(|client| {
+            let packet = client.rows.pop_front()?;
+            Some(client.write(packet))
+        });
+
+        join_all(futures).await;
+        self.schedule_rows();
+    }
+
+    /// Stops sending anyone the rest of their progressive images.
+    fn clear_rows(&mut self) {
+        self.clients.iter_mut().for_each(|x| x.rows.clear());
+    }
+
+    pub async fn broadcast(&mut self, packet: &Packet, exclude: Option<usize>) {
+        let futures = self
+            .clients
+            .iter_mut()
+            .filter(|client| client.initialized() && exclude.is_none_or(|x| client.id != x))
+            .filter(|client| {
+                packet
+                    .capability()
+                    .is_none_or(|x| client.capabilities.contains(x))
+            })
+            .map(|client| client.write(packet.clone()));
+
+        join_all(futures).await;
+    }
+
+    pub fn ready(&self) -> bool {
+        let ready = self.clients.iter().filter(|x| x.ready).count();
+        ready >= 2 && ready == self.clients.iter().filter(|x| x.initialized()).count()
+    }
+
+    pub async fn lobby(&mut self) -> shared::lobby::Clients {
+        let inner: Vec<_> = self
+            .clients
+            .iter()
+            .filter_map(|x| {
+                if !x.initialized() {
+                    return None;
+                }
+                Some(shared::lobby::Client {
+                    id: x.id,
+                    ready: x.ready,
+                    options: x.options.clone()?,
+                })
+            })
+            .collect();
+
+        shared::lobby::Clients::from(inner)
+    }
+
+    pub async fn broadcast_lobby(&mut self, id: usize, action: shared::lobby::Action) {
+        let clients = self.lobby().await;
+        self.broadcast(
+            &Packet::LobbyEvent {
+                action,
+                user: id,
+                lobby: clients,
+            },
+            None,
+        )
+        .await;
+    }
+
+    pub async fn handle(&mut self, message: Message) -> Result<(), Error> {
+        let message = match message {
+            // Whatever state the room is in, the queued up passes are sent or were already dropped.
+            Message::Rows(_) => {
+                self.send_rows().await;
+                return Ok(());
+            }
+            // Clients which showed up mid-game wait for it to end, instead of joining in.
+            Message::Packet(id, Ok(packet))
+                if self.state != State::Lobby && !self[id].initialized() =>
+            {
+                return self.park(id, packet).await;
+            }
+            message => message,
+        };
+
+        match &mut self.state {
+            State::Lobby => lobby::handler(self, message).await?,
+            State::Round(round) => match message {
+                Message::Deadline(deadline) if self.deadline != Some(deadline) => (),
+                Message::GuessingComplete(_) | Message::Deadline(_) => {
+                    self.deadline = None;
+                    let mut round = round.clone();
+                    self.clear_rows();
+                    round::results(&mut round, &mut self.game);
+
+                    self.state = State::Results(round.clone());
+                    self.broadcast(&Packet::Result { results: round }, None)
+                        .await;
+                    eprintln!(
+                        "server(room {}): round finished, showing results",
+                        self.code
+                    );
+                }
+                Message::Packet(id, packet) => match packet {
+                    Ok(Packet::Look { .. } | Packet::Guess { .. })
+                        if !round.players.iter().any(|x| x.id == id) =>
+                    {
+                        eprintln!("server(client {id}): isn't playing this round");
+                    }
+                    Ok(Packet::RequestGameEnd) => {
+                        eprintln!("server(client {id}): return to lobby");
+                        self.return_to_lobby(id).await;
+                    }
+                    Ok(Packet::SoftQuit) => self.soft_kick(id).await?,
+                    Ok(Packet::Look { direction }) => {
+                        eprintln!("server(client {id}): looking {direction:?}");
+                        // Whatever's left of the last image isn't being looked at anymore.
+                        let format = self[id].format();
+                        self[id].rows.clear();
+                        if let Some(Encoded {
+                            width,
+                            height,
+                            image,
+                            passes,
+                        }) = self.panorama.image(format, direction).await
+                        {
+                            self[id].rows = rows(direction, passes);
+                            let packet = Packet::View {
+                                direction,
+                                width,
+                                height,
+                                image,
+                            };
+                            self.send(id, packet).await?;
+                            self.schedule_rows();
+                        }
+                    }
+                    Ok(Packet::Guess { coordinates }) => {
+                        eprintln!("server(client {id}): guessed at {coordinates:?}");
+                        round[id].guess = Some(coordinates);
+                        self[id].rows.clear();
+                        self.check_guesses().await?;
+                        self.broadcast(&Packet::Guessed { player: id }, Some(id))
+                            .await;
+                    }
+                    Ok(other) => self.kick(id, shared::Error::Illegal(other)).await?,
+                    Err(error @ (shared::Error::Io(_) | shared::Error::Close)) => {
+                        self.disconnect(id, error).await?
+                    }
+                    Err(error) => self.kick(id, error).await?,
+                },
+                Message::Expired(token) => self.expire(token).await,
+                Message::Connection(..) | Message::Quit | Message::Rows(_) => (),
+            },
+            State::Results(round) => match message {
+                Message::Packet(id, packet) => match packet {
+                    Ok(Packet::RequestGameEnd) => {
+                        eprintln!("server(client {id}): returning to lobby...");
+                        self.return_to_lobby(id).await;
+                    }
+                    Ok(Packet::WaitingStatus { ready }) => {
+                        eprintln!("server(client {id}): ready");
+                        let round = round.clone();
+
+                        self[id].ready = ready;
+                        self.broadcast_lobby(id, shared::lobby::Action::Ready).await;
+
+                        if self.ready() {
+                            self.advance(id, &round).await?;
+                        }
+                    }
+                    Ok(Packet::SoftQuit) => self.soft_kick(id).await?,
+                    Ok(Packet::Guess { .. }) => {
+                        // Guesses can race with the deadline, so this isn't the client's fault.
+                        eprintln!("server(client {id}): guessed too late");
+                    }
+                    Ok(Packet::Look { .. }) => {
+                        // Neither is looking around just as the round ends.
+                        eprintln!("server(client {id}): looked too late");
+                    }
+                    Ok(other) => self.kick(id, shared::Error::Illegal(other)).await?,
+                    Err(error @ (shared::Error::Io(_) | shared::Error::Close)) => {
+                        self.disconnect(id, error).await?
+                    }
+                    Err(error) => self.kick(id, error).await?,
+                },
+                Message::Expired(token) => self.expire(token).await,
+                Message::Connection(..)
+                | Message::Quit
+                | Message::GuessingComplete(_)
+                | Message::Deadline(_)
+                | Message::Rows(_) => (),
+            },
+        }
+
+        Ok(())
+    }
+}
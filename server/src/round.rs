@@ -2,18 +2,24 @@ use geoutils::Location;
 use shared::RoundResult;
 
 use crate::server::State;
-use crate::{Server, error::Error, images::images};
+use tokio::time::{Instant, sleep_until};
+
+use crate::{Message, error::Error, game::Game, images::Panorama, room::Room};
 use shared::Player;
 
-pub async fn new(server: &mut Server, old: Option<&RoundResult>) -> Result<State, Error> {
-    eprintln!("server: initializing round");
-    server.clients.iter_mut().for_each(|x| x.ready = false);
-    let lobby = server.lobby().await;
-    server
-        .broadcast(&shared::Packet::RoundLoading { lobby }, None)
+/// Starts a new round, following on from `old` if there was one, because of what client `id` did.
+pub async fn new(room: &mut Room, id: usize, old: Option<&RoundResult>) -> Result<State, Error> {
+    eprintln!("server(room {}): initializing round", room.code);
+    room.clients.iter_mut().for_each(|x| x.ready = false);
+    let lobby = room.lobby().await;
+    room.broadcast(&shared::Packet::RoundLoading { lobby }, None)
         .await;
 
-    let number = old.and_then(|x| Some(x.number + 1)).unwrap_or(0);
+    if old.is_none() {
+        room.game = Game::default();
+    }
+
+    let number = old.map(|x| x.number + 1).unwrap_or(0);
     let players: Vec<Player> = if let Some(old_round) = old {
         old_round
             .players
@@ -26,9 +32,9 @@ pub async fn new(server: &mut Server, old: Option<&RoundResult>) -> Result<State
             })
             .collect()
     } else {
-        server
-            .clients
+        room.clients
             .iter()
+            .filter(|c| c.initialized())
             .map(|c| Player {
                 guess: None,
                 points: 0,
@@ -39,20 +45,38 @@ pub async fn new(server: &mut Server, old: Option<&RoundResult>) -> Result<State
     };
 
     eprintln!("server: fetching image...");
-    let (bytes, data) = images().await?;
+    let fetched = room.prefetch.lock().await.next().await;
+    let (slices, data) = match fetched {
+        Ok(fetched) => fetched,
+        Err(error) => {
+            // Everyone can just ready up again, which tries the next image.
+            eprintln!(
+                "server(room {}): couldn't fetch an image: {error}",
+                room.code
+            );
+            room.return_to_lobby(id).await;
+            return Ok(State::Lobby);
+        }
+    };
     eprintln!("server: fetched image from {}", data.address);
+    room.panorama = Panorama::new(slices);
 
-    server
-        .broadcast(
-            &shared::Packet::Round {
-                number,
-                image: bytes[1].clone(),
-            },
-            None,
-        )
-        .await;
+    let deadline = room.config.time_limit.map(|limit| {
+        let deadline = Instant::now() + limit;
+        let tx = room.tx.clone();
+        tokio::spawn(async move {
+            sleep_until(deadline).await;
+            let _ = tx.send(Message::Deadline(deadline)).await;
+        });
+
+        deadline
+    });
+    room.deadline = deadline;
 
-    eprintln!("server: starting round {number}");
+    let seconds = room.config.time_limit.map(|x| x.as_secs() as u32);
+    room.broadcast_round(number, seconds.unwrap_or(0)).await;
+
+    eprintln!("server(room {}): starting round {number}", room.code);
     Ok(State::Round(RoundResult {
         answer: data.coordinates,
         number,
@@ -60,17 +84,21 @@ pub async fn new(server: &mut Server, old: Option<&RoundResult>) -> Result<State
     }))
 }
 
-pub fn results(round: &mut RoundResult) {
+pub fn results(round: &mut RoundResult, game: &mut Game) {
     let answer = Location::new(round.answer.latitude, round.answer.longitude);
 
     for player in &mut round.players {
-        let distance = Location::new(
-            player.guess.unwrap().latitude,
-            player.guess.unwrap().longitude,
-        )
-        .haversine_distance_to(&answer)
-        .meters()
+        let Some(guess) = player.guess else {
+            game.miss(player.id);
+            player.delta = 0;
+            continue;
+        };
+
+        let distance = Location::new(guess.latitude, guess.longitude)
+            .haversine_distance_to(&answer)
+            .meters()
             / 1000.0;
+        game.record(player.id, round.number, distance as f32);
 
         const MAX_SCORE: f64 = 1000.0;
         const BEST_DISTANCE: f64 = 200.0;
@@ -1,18 +1,24 @@
 use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
-    ops::{Index, IndexMut},
+    sync::Arc,
+    time::Duration,
 };
 
-use futures::future::join_all;
-use shared::{PORT, Packet, RoundResult};
+use shared::{DEFAULT_ROOM, PORT, Packet, RoundResult};
 use tokio::{
-    io::AsyncWriteExt,
     net::{TcpListener, TcpStream},
-    sync::mpsc,
+    sync::{Mutex, mpsc},
     task::JoinHandle,
 };
 
-use crate::{Message, client::Client, error::Error, lobby, round};
+use crate::{
+    Message,
+    client::Client,
+    error::Error,
+    lobby,
+    prefetch::Prefetch,
+    room::{self, Room},
+};
 
 #[derive(Debug, PartialEq)]
 pub enum State {
@@ -21,35 +27,28 @@ pub enum State {
     Results(RoundResult),
 }
 
+#[derive(Debug, Default)]
+pub struct Config {
+    /// How long players get to guess before the round is ended for them.
+    pub time_limit: Option<Duration>,
+
+    /// How many rounds are in a game, or [`None`] if games go on forever.
+    pub rounds: Option<usize>,
+
+    /// How long a client which disconnected mid-game can take to resume its session.
+    pub grace: Duration,
+}
+
 pub struct Server {
-    pub clients: Vec<Client>,
-    pub state: State,
+    pub rooms: Vec<Room>,
+    pub config: Arc<Config>,
+    pub prefetch: Arc<Mutex<Prefetch>>,
     pub tx: mpsc::Sender<Message>,
     rx: mpsc::Receiver<Message>,
     listener: JoinHandle<Result<(), Error>>,
     id_counter: usize,
 }
 
-impl IndexMut<usize> for Server {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        self.clients
-            .iter_mut()
-            .find(|x| x.id == index)
-            .expect(&format!("couldn't find client {index}"))
-    }
-}
-
-impl Index<usize> for Server {
-    type Output = Client;
-
-    fn index(&self, index: usize) -> &Self::Output {
-        self.clients
-            .iter()
-            .find(|x| x.id == index)
-            .expect(&format!("couldn't find client {index}"))
-    }
-}
-
 impl Drop for Server {
     fn drop(&mut self) {
         self.listener.abort();
@@ -69,191 +68,210 @@ impl Server {
         let id = self.id_counter;
         self.id_counter += 1;
 
+        // Even if the default room is mid-game, this is where a client resumes its session from,
+        // and otherwise it just waits there for the game to end.
         let client = Client::new(id, self.tx.clone(), socket).await?;
-        self.clients.push(client);
+        self.rooms[0].clients.push(client);
 
         eprintln!("server: new client at {addr:?} with id {id}");
 
         Ok(())
     }
 
-    pub async fn return_to_lobby(&mut self, id: usize) {
-        self.state = State::Lobby;
-        self.broadcast_lobby(id, shared::lobby::Action::Return)
-            .await;
+    /// Finds the index of the room which a client is in.
+    pub fn room(&self, id: usize) -> Option<usize> {
+        self.rooms.iter().position(|x| x.contains(id))
     }
 
-    pub async fn verify(&mut self, id: usize) {
-        if self.clients.iter().filter(|x| x.initialized()).count() < 2 {
-            eprintln!("server: not enough players, returning to lobby...");
-            self.return_to_lobby(id).await;
-        }
+    fn add_room(&mut self, code: String) -> usize {
+        let room = Room::new(
+            code,
+            self.config.clone(),
+            self.prefetch.clone(),
+            self.tx.clone(),
+        );
+        self.rooms.push(room);
+        self.rooms.len() - 1
     }
 
-    pub async fn soft_kick(&mut self, client: usize) -> Result<(), Error> {
-        eprintln!("server(client {client}): soft quit");
-        self[client].options = None;
-        self[client].ready = false;
-        self.verify(client).await;
+    /// Moves a client into another room, as long as it's in the lobby.
+    pub async fn move_client(&mut self, id: usize, from: usize, to: usize) -> Result<(), Error> {
+        let mut parked = None;
+        if from != to {
+            // A client which was waiting for a game to end doesn't have to anymore.
+            parked = self.rooms[from].take_parked(id);
+            let Some(mut client) = self.rooms[from].leave(id).await? else {
+                return Ok(());
+            };
+
+            client.ready = false;
+            self.rooms[to].clients.push(client);
+        }
+
+        let room = &mut self.rooms[to];
+        eprintln!("server(client {id}): joined room {}", room.code);
 
-        if self.state == State::Lobby && self.ready() {
-            self.state = round::new(self, None).await?;
-        } else {
-            self.verify(client).await;
+        let code = room.code.clone();
+        let lobby = room.lobby().await;
+        room.send(id, Packet::Room { code, lobby }).await?;
+        if !room.contains(id) {
+            return Ok(());
+        }
+
+        if let Some(init) = parked {
+            lobby::handler(room, Message::Packet(id, Ok(init))).await?;
+        } else if room[id].initialized() {
+            room.broadcast_lobby(id, shared::lobby::Action::Join).await;
         }
 
         Ok(())
     }
 
-    pub async fn kick(&mut self, client: usize, error: shared::Error) -> Result<(), Error> {
-        eprintln!("server(client {client}): removed: {error}");
-        if let Some(index) = self.clients.iter().position(|x| x.id == client) {
-            self.clients.remove(index);
+    /// Handles a client trying to create or join a room.
+    pub async fn join(&mut self, id: usize, packet: Packet) -> Result<(), Error> {
+        let Some(from) = self.room(id) else {
+            return Ok(());
         };
 
-        if self.state == State::Lobby {
-            self.broadcast_lobby(client, shared::lobby::Action::Leave)
+        // Clients which are in the middle of a game can't just walk out of it.
+        if self.rooms[from].state != State::Lobby && self.rooms[from][id].initialized() {
+            return self.rooms[from]
+                .kick(id, shared::Error::Illegal(packet))
                 .await;
+        }
 
-            if self.ready() {
-                self.state = round::new(self, None).await?;
+        let to = match packet {
+            Packet::JoinRoom { code } => {
+                let to = self
+                    .rooms
+                    .iter()
+                    .position(|x| x.code == code && x.state == State::Lobby);
+
+                let Some(to) = to else {
+                    eprintln!("server(client {id}): room {code} is unavailable");
+                    return self.rooms[from]
+                        .send(id, Packet::RoomUnavailable { code })
+                        .await;
+                };
+
+                to
             }
-        } else {
-            self.verify(client).await;
-        }
+            _ => {
+                let mut code = room::code();
+                while self.rooms.iter().any(|x| x.code == code) {
+                    code = room::code();
+                }
 
-        Ok(())
+                eprintln!("server(client {id}): created room {code}");
+                self.add_room(code)
+            }
+        };
+
+        self.move_client(id, from, to).await
     }
 
-    pub async fn broadcast(&mut self, packet: &Packet, exclude: Option<usize>) {
-        let futures = self
-            .clients
-            .iter_mut()
-            .filter(|client| client.initialized() && !exclude.is_some_and(|x| client.id == x))
-            .map(|client| client.write(packet.clone()));
+    /// Handles a client trying to resume a session which it lost mid-game.
+    pub async fn resume(&mut self, id: usize, token: u64) -> Result<(), Error> {
+        let Some(from) = self.room(id) else {
+            return Ok(());
+        };
 
-        join_all(futures).await;
-    }
+        if self.rooms[from][id].initialized() {
+            return self.rooms[from]
+                .kick(id, shared::Error::Illegal(Packet::Resume { token }))
+                .await;
+        }
 
-    pub fn ready(&self) -> bool {
-        let ready = self.clients.iter().filter(|x| x.ready).count();
-        ready >= 2 && ready == self.clients.iter().filter(|x| x.initialized()).count()
-    }
+        let found = self.rooms.iter().enumerate().find_map(|(i, room)| {
+            let session = room.sessions.iter().position(|x| x.token == token)?;
+            Some((i, session))
+        });
 
-    pub async fn lobby(&mut self) -> shared::lobby::Clients {
-        let inner: Vec<_> = self
-            .clients
-            .iter()
-            .filter_map(|x| {
-                if !x.initialized() {
-                    return None;
-                }
-                Some(shared::lobby::Client {
-                    id: x.id,
-                    ready: x.ready,
-                    options: x.options.clone()?,
-                })
-            })
-            .collect();
-
-        shared::lobby::Clients::from(inner)
-    }
+        let Some((to, session)) = found else {
+            eprintln!("server(client {id}): no session to resume");
+            return self.rooms[from].send(id, Packet::ResumeFailed).await;
+        };
+
+        // The session is still counted as a player while the new connection leaves its room.
+        let Some(mut client) = self.rooms[from].leave(id).await? else {
+            return Ok(());
+        };
+        let session = self.rooms[to].sessions.remove(session);
+
+        eprintln!("server(client {id}): resuming as client {}", session.id);
+        client.resume(session);
 
-    pub async fn broadcast_lobby(&mut self, id: usize, action: shared::lobby::Action) {
-        let clients = self.lobby().await;
-        self.broadcast(
-            &Packet::LobbyEvent {
-                action,
-                user: id,
-                lobby: clients,
-            },
-            None,
-        )
-        .await;
+        let id = client.id;
+        self.rooms[to].clients.push(client);
+        self.rooms[to].resume(id).await
     }
 
-    pub async fn new() -> Result<Self, Error> {
-        let (tx, rx) = mpsc::channel(8);
+    pub async fn new(prefetch: Prefetch, config: Config) -> Result<Self, Error> {
         let address = SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), PORT);
         let tcp = TcpListener::bind(address).await?;
+        Ok(Self::with_listener(tcp, prefetch, config))
+    }
+
+    /// Serves clients from a listener which is already bound, rather than the usual port.
+    pub fn with_listener(tcp: TcpListener, prefetch: Prefetch, config: Config) -> Self {
+        let (tx, rx) = mpsc::channel(8);
         let listener = tokio::spawn(Self::listen(tcp, tx.clone()));
 
-        Ok(Self {
+        let mut server = Self {
             tx,
             rx,
-            clients: Vec::new(),
+            rooms: Vec::new(),
             id_counter: 0,
             listener,
-            state: State::Lobby,
-        })
+            prefetch: Arc::new(Mutex::new(prefetch)),
+            config: Arc::new(config),
+        };
+        server.add_room(DEFAULT_ROOM.to_owned());
+
+        server
     }
 
     pub async fn run(&mut self) -> Result<(), Error> {
         while let Some(message) = self.rx.recv().await {
-            if matches!(message, Message::Quit) {
-                break;
+            let room = match &message {
+                Message::Quit => break,
+                Message::Connection(..) => None,
+                Message::Packet(id, _) => self.room(*id),
+                Message::GuessingComplete(code) | Message::Rows(code) => {
+                    self.rooms.iter().position(|x| &x.code == code)
+                }
+                Message::Deadline(deadline) => self
+                    .rooms
+                    .iter()
+                    .position(|x| x.deadline == Some(*deadline)),
+                Message::Expired(token) => self
+                    .rooms
+                    .iter()
+                    .position(|x| x.sessions.iter().any(|x| x.token == *token)),
+            };
+
+            match message {
+                Message::Connection(socket, address) => self.client(socket, address).await?,
+                Message::Packet(
+                    id,
+                    Ok(packet @ (Packet::CreateRoom | Packet::JoinRoom { .. })),
+                ) => self.join(id, packet).await?,
+                Message::Packet(id, Ok(Packet::Resume { token })) => self.resume(id, token).await?,
+                message => {
+                    if let Some(room) = room {
+                        self.rooms[room].handle(message).await?;
+                    }
+                }
             }
 
-            match &mut self.state {
-                State::Lobby => lobby::handler(self, message).await?,
-                State::Round(round) => match message {
-                    Message::GuessingComplete => {
-                        let mut round = round.clone();
-                        round::results(&mut round);
-
-                        self.state = State::Results(round.clone());
-                        self.broadcast(&Packet::Result { results: round }, None)
-                            .await;
-                        eprintln!("server: round finished, showing results");
-                    }
-                    Message::Connection(mut stream, _addr) => stream.shutdown().await?,
-                    Message::Packet(id, packet) => match packet {
-                        Ok(Packet::RequestGameEnd) => {
-                            eprintln!("server(client {id}): return to lobby");
-                            self.return_to_lobby(id).await;
-                        }
-                        Ok(Packet::SoftQuit) => self.soft_kick(id).await?,
-                        Ok(Packet::Guess { coordinates }) => {
-                            eprintln!("server(client {id}): guessed at {coordinates:?}");
-                            round[id].guess = Some(coordinates);
-                            if round.players.iter().all(|x| x.guess.is_some()) {
-                                self.tx.send(Message::GuessingComplete).await?;
-                            }
-
-                            self.broadcast(&Packet::Guessed { player: id }, Some(id))
-                                .await;
-                        }
-                        Ok(other) => self.kick(id, shared::Error::Illegal(other)).await?,
-                        Err(error) => self.kick(id, error).await?,
-                    },
-                    Message::Quit => return Ok(()),
-                },
-                State::Results(round) => match message {
-                    Message::Packet(id, packet) => match packet {
-                        Ok(Packet::RequestGameEnd) => {
-                            eprintln!("server(client {id}): returning to lobby...");
-                            self.return_to_lobby(id).await;
-                        }
-                        Ok(Packet::WaitingStatus { ready }) => {
-                            eprintln!("server(client {id}): ready");
-                            let round = round.clone();
-
-                            self[id].ready = ready;
-                            self.broadcast_lobby(id, shared::lobby::Action::Ready).await;
-
-                            if self.ready() {
-                                eprintln!("server: all ready, starting new round");
-                                self.state = round::new(self, Some(&round)).await?;
-                            }
-                        }
-                        Ok(Packet::SoftQuit) => self.soft_kick(id).await?,
-                        Ok(other) => self.kick(id, shared::Error::Illegal(other)).await?,
-                        Err(error) => self.kick(id, error).await?,
-                    },
-                    Message::Connection(mut stream, _addr) => stream.shutdown().await?,
-                    Message::Quit | Message::GuessingComplete => return Ok(()),
-                },
+            for room in &mut self.rooms {
+                room.unpark().await?;
             }
+
+            // The default room always sticks around, even if it's empty.
+            self.rooms.retain(|x| {
+                x.code == DEFAULT_ROOM || !x.clients.is_empty() || !x.sessions.is_empty()
+            });
         }
 
         Ok(())
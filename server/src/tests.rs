@@ -0,0 +1,447 @@
+//! Plays games against a server on a free port with the demo image from `misc/`, checking how
+//! rooms move between their lobby, rounds and results the way that clients see it.
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use shared::{
+    BufferedSplitExt, Capabilities, ClientOptions, Color, Direction, Display, Packet,
+    PacketReadExt, PacketWriteExt, Reader, Reason, Writer,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    sync::OnceCell,
+    time::timeout,
+};
+
+use crate::{
+    images::{Provider, bundle::Bundle},
+    prefetch::Prefetch,
+    server::{Config, Server},
+};
+
+/// How long to wait on any one packet, which is mostly how long a debug build takes on an image.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The demo image as a bundle, which is only built once since slicing it up is slow in debug
+/// builds, but reading it back isn't.
+async fn bundle() -> &'static Path {
+    static BUNDLE: OnceCell<PathBuf> = OnceCell::const_new();
+    BUNDLE
+        .get_or_init(|| async {
+            let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("../misc");
+            let output = std::env::temp_dir().join(format!("geoterm-{}.geob", std::process::id()));
+            Bundle::build(&directory, &output).await.unwrap();
+            output
+        })
+        .await
+}
+
+/// Starts a server, and returns where it's listening.
+async fn serve(config: Config) -> SocketAddr {
+    let bundle = Bundle::open(bundle().await).await.unwrap();
+    let prefetch = Prefetch::new(Provider::Bundle(bundle), 1);
+    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+        .await
+        .unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let mut server = Server::with_listener(listener, prefetch, config);
+    tokio::spawn(async move { server.run().await });
+    address
+}
+
+/// Without [`Capabilities::STANDINGS`], the end of a game is only a lobby event.
+fn init(user: &str, capabilities: Capabilities) -> Packet {
+    Packet::Init {
+        capabilities: capabilities | Capabilities::STANDINGS,
+        options: ClientOptions {
+            color: Color::Cyan,
+            user: user.to_owned(),
+        },
+        // Small images keep the rounds quick.
+        display: Display {
+            width: 32,
+            height: 16,
+            ..Display::default()
+        },
+    }
+}
+
+/// A client, as far as the tests need one.
+struct Connection {
+    reader: Reader,
+    writer: Writer,
+    id: usize,
+    token: u64,
+}
+
+impl Connection {
+    async fn connect(address: SocketAddr) -> Self {
+        let (reader, writer) = TcpStream::connect(address).await.unwrap().buffered_split();
+        Self {
+            reader,
+            writer,
+            id: 0,
+            token: 0,
+        }
+    }
+
+    /// Connects and joins the lobby of the default room.
+    async fn join(address: SocketAddr, user: &str) -> Self {
+        let mut connection = Self::connect(address).await;
+        connection.send(init(user, Capabilities::NONE)).await;
+        connection.confirmed().await;
+        connection
+    }
+
+    /// Waits for a [`Packet::Confirmed`], and keeps the id and token from it.
+    async fn confirmed(&mut self) {
+        let Packet::Confirmed { id, token, .. } =
+            self.expect(|x| matches!(x, Packet::Confirmed { .. })).await
+        else {
+            unreachable!();
+        };
+
+        self.id = id;
+        self.token = token;
+    }
+
+    async fn send(&mut self, packet: Packet) {
+        self.writer.write_packet(packet).await.unwrap();
+        self.writer.flush().await.unwrap();
+    }
+
+    async fn recv(&mut self) -> Packet {
+        timeout(TIMEOUT, self.reader.read_packet())
+            .await
+            .expect("timed out waiting for a packet")
+            .unwrap()
+    }
+
+    /// Skips ahead to the next packet that `matches`, since there are plenty of lobby events
+    /// and images along the way which most tests don't care about.
+    async fn expect(&mut self, matches: impl Fn(&Packet) -> bool) -> Packet {
+        loop {
+            let packet = self.recv().await;
+            if matches(&packet) {
+                return packet;
+            }
+        }
+    }
+
+    /// Checks that the server doesn't send anything for a little while.
+    async fn silent(&mut self) {
+        let received = timeout(Duration::from_millis(500), self.reader.read_packet()).await;
+        assert!(received.is_err(), "unexpected packet: {received:?}");
+    }
+}
+
+/// Has two players ready up in the default room, and waits for the first round to start.
+async fn play(address: SocketAddr) -> [Connection; 2] {
+    let mut players = [
+        Connection::join(address, "a").await,
+        Connection::join(address, "b").await,
+    ];
+
+    for player in &mut players {
+        player.send(Packet::WaitingStatus { ready: true }).await;
+    }
+    for player in &mut players {
+        player.expect(|x| matches!(x, Packet::Round { .. })).await;
+    }
+
+    players
+}
+
+#[tokio::test]
+async fn no_new_rooms_mid_game() {
+    let address = serve(Config::default()).await;
+    let [mut a, _b] = play(address).await;
+
+    a.send(Packet::CreateRoom).await;
+    let packet = a.expect(|x| matches!(x, Packet::Error { .. })).await;
+    assert!(matches!(
+        packet,
+        Packet::Error {
+            reason: Reason::Illegal,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn new_clients_can_leave_mid_game() {
+    let address = serve(Config::default()).await;
+    let _players = play(address).await;
+
+    // Someone waiting on the game in the default room can start their own instead.
+    let mut c = Connection::connect(address).await;
+    c.send(init("c", Capabilities::NONE)).await;
+    c.silent().await;
+    c.send(Packet::CreateRoom).await;
+    c.expect(|x| matches!(x, Packet::Room { .. })).await;
+    c.confirmed().await;
+
+    let mut d = Connection::connect(address).await;
+    d.send(Packet::CreateRoom).await;
+    d.expect(|x| matches!(x, Packet::Room { .. })).await;
+}
+
+#[tokio::test]
+async fn rooms_are_unavailable_mid_game() {
+    let address = serve(Config::default()).await;
+    let mut a = Connection::join(address, "a").await;
+    a.send(Packet::CreateRoom).await;
+    let Packet::Room { code, .. } = a.expect(|x| matches!(x, Packet::Room { .. })).await else {
+        unreachable!();
+    };
+
+    let mut b = Connection::join(address, "b").await;
+    b.send(Packet::JoinRoom { code: code.clone() }).await;
+    b.expect(|x| matches!(x, Packet::Room { .. })).await;
+
+    for player in [&mut a, &mut b] {
+        player.send(Packet::WaitingStatus { ready: true }).await;
+    }
+    a.expect(|x| matches!(x, Packet::Round { .. })).await;
+
+    let mut c = Connection::join(address, "c").await;
+    c.send(Packet::JoinRoom { code: code.clone() }).await;
+    let packet = c
+        .expect(|x| matches!(x, Packet::RoomUnavailable { .. }))
+        .await;
+    assert_eq!(packet, Packet::RoomUnavailable { code });
+}
+
+#[tokio::test]
+async fn new_clients_wait_for_the_lobby() {
+    let address = serve(Config::default()).await;
+    let [mut a, _b] = play(address).await;
+
+    // Guessing without being in the game just gets a client kicked.
+    let mut c = Connection::connect(address).await;
+    c.send(Packet::Guess {
+        coordinates: Default::default(),
+    })
+    .await;
+    let packet = c.recv().await;
+    assert!(matches!(
+        packet,
+        Packet::Error {
+            reason: Reason::Illegal,
+            ..
+        }
+    ));
+
+    let mut d = Connection::connect(address).await;
+    d.send(init("d", Capabilities::NONE)).await;
+    d.silent().await;
+
+    a.send(Packet::RequestGameEnd).await;
+    d.confirmed().await;
+}
+
+#[tokio::test]
+async fn deadline_scores_the_rest_zero() {
+    let address = serve(Config {
+        time_limit: Some(Duration::from_secs(1)),
+        ..Config::default()
+    })
+    .await;
+    let [mut a, mut b] = play(address).await;
+
+    a.send(Packet::Guess {
+        coordinates: Default::default(),
+    })
+    .await;
+
+    // Nobody else guesses, so it's the deadline which ends the round.
+    let Packet::Result { results } = b.expect(|x| matches!(x, Packet::Result { .. })).await else {
+        unreachable!();
+    };
+    let player = |id| results.players.iter().find(|x| x.id == id).unwrap();
+    assert!(player(a.id).guess.is_some());
+    assert!(player(b.id).guess.is_none());
+    assert_eq!((player(b.id).points, player(b.id).delta), (0, 0));
+
+    // Looking around just as the round ended isn't held against anyone.
+    b.send(Packet::Look {
+        direction: Direction::Left,
+    })
+    .await;
+    b.silent().await;
+}
+
+#[tokio::test]
+async fn game_over_after_rounds() {
+    let address = serve(Config {
+        rounds: Some(2),
+        ..Config::default()
+    })
+    .await;
+    let mut players = play(address).await;
+
+    for number in 0..2 {
+        for player in &mut players {
+            player
+                .send(Packet::Guess {
+                    coordinates: Default::default(),
+                })
+                .await;
+        }
+        for player in &mut players {
+            let Packet::Result { results } =
+                player.expect(|x| matches!(x, Packet::Result { .. })).await
+            else {
+                unreachable!();
+            };
+            assert_eq!(results.number, number);
+            player.send(Packet::WaitingStatus { ready: true }).await;
+        }
+
+        // Readying up after the last round ends the game instead of starting another.
+        let next = |x: &Packet| matches!(x, Packet::Round { .. } | Packet::GameOver { .. });
+        for player in &mut players {
+            match player.expect(next).await {
+                Packet::GameOver { results } => {
+                    assert_eq!(number, 1);
+                    assert_eq!(results.standings.len(), 2);
+                }
+                _ => assert_eq!(number, 0),
+            }
+        }
+    }
+
+    let packet = players[0]
+        .expect(|x| matches!(x, Packet::LobbyEvent { .. }))
+        .await;
+    assert!(matches!(
+        packet,
+        Packet::LobbyEvent {
+            action: shared::lobby::Action::Return,
+            ..
+        }
+    ));
+}
+
+/// Drops a player's connection, and waits for the server to notice.
+async fn drop_connection(player: Connection) -> (usize, u64) {
+    let session = (player.id, player.token);
+    drop(player);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    session
+}
+
+#[tokio::test]
+async fn resume_within_grace() {
+    let address = serve(Config {
+        grace: Duration::from_secs(30),
+        ..Config::default()
+    })
+    .await;
+    let [a, _b] = play(address).await;
+    let (id, token) = drop_connection(a).await;
+
+    let mut a = Connection::connect(address).await;
+    a.send(Packet::Resume { token }).await;
+    a.confirmed().await;
+    assert_eq!(a.id, id);
+    assert_ne!(a.token, token);
+
+    // It's caught up with the round that's still going on.
+    a.expect(|x| matches!(x, Packet::Round { .. })).await;
+}
+
+#[tokio::test]
+async fn resume_after_grace() {
+    let address = serve(Config {
+        grace: Duration::from_millis(100),
+        ..Config::default()
+    })
+    .await;
+    let [a, _b] = play(address).await;
+    let (_, token) = drop_connection(a).await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut a = Connection::connect(address).await;
+    a.send(Packet::Resume { token }).await;
+    assert_eq!(a.recv().await, Packet::ResumeFailed);
+}
+
+#[tokio::test]
+async fn missed_rounds_rank_last() {
+    let address = serve(Config {
+        time_limit: Some(Duration::from_secs(1)),
+        rounds: Some(1),
+        ..Config::default()
+    })
+    .await;
+    let [mut a, mut b] = play(address).await;
+
+    a.send(Packet::Guess {
+        coordinates: Default::default(),
+    })
+    .await;
+    for player in [&mut a, &mut b] {
+        player.expect(|x| matches!(x, Packet::Result { .. })).await;
+        player.send(Packet::WaitingStatus { ready: true }).await;
+    }
+
+    let Packet::GameOver { results } = a.expect(|x| matches!(x, Packet::GameOver { .. })).await
+    else {
+        unreachable!();
+    };
+    let [first, last] = results.standings.as_slice() else {
+        panic!("expected two standings, got {:?}", results.standings);
+    };
+    assert_eq!((first.id, last.id), (a.id, b.id));
+    assert!(last.distance > first.distance);
+}
+
+#[tokio::test]
+async fn rows_follow_the_view_until_guessing() {
+    let address = serve(Config::default()).await;
+    let mut a = Connection::connect(address).await;
+    a.send(init("a", Capabilities::PROGRESSIVE)).await;
+    a.confirmed().await;
+    let mut b = Connection::join(address, "b").await;
+    for player in [&mut a, &mut b] {
+        player.send(Packet::WaitingStatus { ready: true }).await;
+    }
+
+    // The image only comes in the passes after the round starts.
+    let Packet::Round { image, .. } = a.expect(|x| matches!(x, Packet::Round { .. })).await else {
+        unreachable!();
+    };
+    assert!(image.is_empty());
+    assert!(matches!(a.recv().await, Packet::Rows { first: 0, .. }));
+
+    // Looking somewhere else drops the rest of the old image for the new one.
+    a.send(Packet::Look {
+        direction: Direction::Left,
+    })
+    .await;
+    let Packet::View { image, .. } = a.expect(|x| matches!(x, Packet::View { .. })).await else {
+        unreachable!();
+    };
+    assert!(image.is_empty());
+    assert!(matches!(
+        a.recv().await,
+        Packet::Rows {
+            direction: Direction::Left,
+            first: 0,
+            ..
+        }
+    ));
+
+    a.send(Packet::Guess {
+        coordinates: Default::default(),
+    })
+    .await;
+    a.silent().await;
+}
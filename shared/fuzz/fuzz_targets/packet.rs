@@ -0,0 +1,34 @@
+//! Feeds arbitrary bytes to the packet deserializer, which should never panic.
+//! Run it from `shared/` with `cargo +nightly fuzz run packet`.
+
+#![no_main]
+
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+use shared::{
+    Packet,
+    deserializers::Deserialize,
+    serializers::Serialize,
+    sync::{Decode, Encode},
+};
+
+fuzz_target!(|data: &[u8]| {
+    // Both codecs should agree on what's a valid packet.
+    let mut reader = data;
+    let decoded = Packet::decode(&mut reader).ok();
+    let mut reader = data;
+    let Ok(packet) = block_on(Packet::deserialize(&mut reader)) else {
+        assert!(decoded.is_none(), "only the sync codec could read a packet");
+        return;
+    };
+    let decoded = decoded.expect("only the async codec could read a packet");
+
+    // Anything that can be read should also be writable again. Floats might be NaN,
+    // so the packets are compared by what they're written as rather than directly.
+    let mut buf = Vec::new();
+    block_on(packet.serialize(&mut buf)).expect("failed to serialize a deserialized packet");
+
+    let mut encoded = Vec::new();
+    decoded.encode(&mut encoded);
+    assert_eq!(buf, encoded);
+});
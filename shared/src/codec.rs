@@ -0,0 +1,1246 @@
+//! Generated from `protocol.json` by `cargo run -p codegen`, so don't edit this by hand.
+
+use alloc::string::String;
+
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    BestGuess, Capabilities, ClientOptions, Coordinate, Display, Error, GameResult, Limits, Player,
+    RoundResult, Standing, lobby,
+    sync::{Decode, Encode, ToFixed, bytes, fixed, list, optional, version},
+};
+
+/// Version of the protocol, which is checked in [`Packet::Init`] and [`Packet::Confirmed`].
+/// This should be bumped whenever the layout of an existing packet changes.
+pub const PROTOCOL_VERSION: u16 = 4;
+
+/// How many characters long room codes are.
+pub const ROOM_CODE_LEN: usize = 4;
+
+impl Capabilities {
+    /// Looking around with [`Packet::Look`] and [`Packet::View`].
+    pub const LOOK: Self = Self(1 << 0);
+
+    /// Final standings in [`Packet::GameOver`].
+    pub const STANDINGS: Self = Self(1 << 1);
+
+    /// Switching rooms with [`Packet::CreateRoom`] and [`Packet::JoinRoom`].
+    pub const ROOMS: Self = Self(1 << 2);
+
+    /// Resuming a dropped session with [`Packet::Resume`].
+    pub const RESUME: Self = Self(1 << 3);
+
+    /// Hearing from a transponder that it's reconnecting with [`Packet::Disconnected`].
+    pub const RECONNECT: Self = Self(1 << 4);
+
+    /// Run-length encoded images in [`Packet::Round`] and [`Packet::View`].
+    pub const RLE: Self = Self(1 << 5);
+
+    /// Images sent a pass at a time with [`Packet::Rows`].
+    pub const PROGRESSIVE: Self = Self(1 << 6);
+
+    pub const ALL: Self = Self(0b1111111);
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[repr(u8)]
+pub enum Action {
+    Join = 0,
+    Return,
+    Leave,
+    Ready,
+}
+
+impl TryFrom<u8> for Action {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Action::Join),
+            1 => Ok(Action::Return),
+            2 => Ok(Action::Leave),
+            3 => Ok(Action::Ready),
+            other => Err(Error::Invalid("lobby action", other)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[repr(u8)]
+pub enum Color {
+    Red = 0,
+    Green,
+    Blue,
+    Cyan,
+    Magenta,
+    Yellow,
+}
+
+impl TryFrom<u8> for Color {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Color::Red),
+            1 => Ok(Color::Green),
+            2 => Ok(Color::Blue),
+            3 => Ok(Color::Cyan),
+            4 => Ok(Color::Magenta),
+            5 => Ok(Color::Yellow),
+            other => Err(Error::Invalid("color", other)),
+        }
+    }
+}
+
+/// Which of the three slices of a panorama is being looked at.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum Direction {
+    Left = 0,
+    #[default]
+    Center,
+    Right,
+}
+
+impl TryFrom<u8> for Direction {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Direction::Left),
+            1 => Ok(Direction::Center),
+            2 => Ok(Direction::Right),
+            other => Err(Error::Invalid("direction", other)),
+        }
+    }
+}
+
+/// Why the server is about to remove a client, sent in [`Packet::Error`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[repr(u8)]
+pub enum Reason {
+    /// The client speaks a different protocol version.
+    Version = 0,
+
+    /// The client sent a packet with a tag that the server doesn't know about.
+    Unknown,
+
+    /// The client sent a packet which doesn't make sense at that point.
+    Illegal,
+
+    /// The client sent a packet with a body that couldn't be read.
+    Malformed,
+}
+
+impl TryFrom<u8> for Reason {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Reason::Version),
+            1 => Ok(Reason::Unknown),
+            2 => Ok(Reason::Illegal),
+            3 => Ok(Reason::Malformed),
+            other => Err(Error::Invalid("reason", other)),
+        }
+    }
+}
+
+/// How the pixels of an image are laid out, row by row from the top left.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum Depth {
+    /// One byte per pixel, with 3 bits of red, 3 of green and 2 of blue.
+    #[default]
+    Rgb332 = 0,
+
+    /// Three bytes per pixel, being red, green and blue.
+    Rgb888,
+
+    /// One byte per pixel, indexing a palette of 256 `Rgb888` colors that comes first.
+    Indexed,
+}
+
+impl TryFrom<u8> for Depth {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Depth::Rgb332),
+            1 => Ok(Depth::Rgb888),
+            2 => Ok(Depth::Indexed),
+            other => Err(Error::Invalid("depth", other)),
+        }
+    }
+}
+
+/// How colors that the depth can't show are made up for.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum Dither {
+    /// Every pixel gets the closest color there is.
+    #[default]
+    None = 0,
+
+    /// The difference is spread onto the pixels which come after it.
+    FloydSteinberg,
+
+    /// Each pixel is nudged by a repeating 4x4 pattern first.
+    Ordered,
+}
+
+impl TryFrom<u8> for Dither {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Dither::None),
+            1 => Ok(Dither::FloydSteinberg),
+            2 => Ok(Dither::Ordered),
+            other => Err(Error::Invalid("dither", other)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Packet {
+    /// Initialize client session.
+    Init {
+        /// Features the client supports.
+        capabilities: Capabilities,
+        /// Client configuration data.
+        options: ClientOptions,
+        /// What images the client can show.
+        display: Display,
+    },
+    /// Server acknowledgment with client and lobby info.
+    Confirmed {
+        /// Features from [`Packet::Init`] which the server supports too.
+        capabilities: Capabilities,
+        /// Assigned client ID.
+        id: usize,
+        /// Secret for resuming the session.
+        token: u64,
+        /// Echo of initialized client options.
+        options: ClientOptions,
+        /// Current lobby state.
+        lobby: lobby::Clients,
+    },
+    /// Lobby action or status change.
+    LobbyEvent {
+        /// What happened.
+        action: Action,
+        /// Client ID affected.
+        user: usize,
+        /// Updated lobby snapshot.
+        lobby: lobby::Clients,
+    },
+    /// Updates readiness status.
+    WaitingStatus {
+        /// `true` if ready for next round.
+        ready: bool,
+    },
+    /// Indicates round loading, includes lobby info.
+    RoundLoading {
+        /// Lobby state before the round begins.
+        lobby: lobby::Clients,
+    },
+    /// Round image and number sent to players.
+    Round {
+        /// Round number.
+        number: usize,
+        /// Seconds to guess, `0` if unlimited.
+        deadline: u32,
+        /// Width of `image`, in pixels.
+        width: u16,
+        /// Height of `image`, in pixels.
+        height: u16,
+        /// Image data, fitted to the client's `Display`.
+        image: Bytes,
+    },
+    /// Player submits a coordinate guess.
+    Guess {
+        /// Player’s guessed location.
+        coordinates: Coordinate,
+    },
+    /// Indicates a player has made a guess.
+    Guessed {
+        /// ID of the player who guessed.
+        player: usize,
+    },
+    /// Sends round results and updated scores.
+    Result {
+        /// Results, scores, and player guesses.
+        results: RoundResult,
+    },
+    /// Requests to end the current game or return to lobby.
+    RequestGameEnd,
+    /// Leaves the game without disconnecting.
+    SoftQuit,
+    /// Requests another slice of the round's panorama.
+    Look {
+        /// Slice to view.
+        direction: Direction,
+    },
+    /// A requested slice of the round's panorama.
+    View {
+        /// Which slice this is.
+        direction: Direction,
+        /// Width of `image`, in pixels.
+        width: u16,
+        /// Height of `image`, in pixels.
+        height: u16,
+        /// Image data, fitted to the client's `Display`.
+        image: Bytes,
+    },
+    /// Final standings once the last round is over.
+    GameOver {
+        /// Final rankings of the game.
+        results: GameResult,
+    },
+    /// Creates a new room and moves the client into it.
+    CreateRoom,
+    /// Moves the client into an existing room.
+    JoinRoom {
+        /// Room code, made of `A`-`Z` only.
+        code: String,
+    },
+    /// The room which the client is now in.
+    Room {
+        /// Code of the room.
+        code: String,
+        /// Current state of its lobby.
+        lobby: lobby::Clients,
+    },
+    /// The requested room doesn't exist or is in a game.
+    RoomUnavailable {
+        /// The requested code.
+        code: String,
+    },
+    /// Resumes a session after the connection dropped.
+    Resume {
+        /// The token from the last [`Packet::Confirmed`] it got.
+        token: u64,
+    },
+    /// There's no session to resume with that token.
+    ResumeFailed,
+    /// Why the server is about to remove the client.
+    Error {
+        /// Why the client is being removed.
+        reason: Reason,
+        /// Protocol version of the server, mostly useful for `VERSION`.
+        version: u16,
+    },
+    /// The transponder lost the server and is reconnecting.
+    Disconnected,
+    /// One pass over the rows of the last [`Packet::Round`] or [`Packet::View`] image.
+    Rows {
+        /// Which slice the rows belong to.
+        direction: Direction,
+        /// First row in `image`.
+        first: u16,
+        /// How many rows apart the rows in `image` are.
+        step: u16,
+        /// Every row of the pass, top to bottom.
+        image: Bytes,
+    },
+}
+
+impl Packet {
+    pub fn tag(&self) -> u8 {
+        match self {
+            Packet::Init { .. } => 1,
+            Packet::Confirmed { .. } => 2,
+            Packet::LobbyEvent { .. } => 3,
+            Packet::WaitingStatus { .. } => 4,
+            Packet::RoundLoading { .. } => 5,
+            Packet::Round { .. } => 6,
+            Packet::Guess { .. } => 7,
+            Packet::Guessed { .. } => 8,
+            Packet::Result { .. } => 9,
+            Packet::RequestGameEnd => 10,
+            Packet::SoftQuit => 11,
+            Packet::Look { .. } => 12,
+            Packet::View { .. } => 13,
+            Packet::GameOver { .. } => 14,
+            Packet::CreateRoom => 15,
+            Packet::JoinRoom { .. } => 16,
+            Packet::Room { .. } => 17,
+            Packet::RoomUnavailable { .. } => 18,
+            Packet::Resume { .. } => 19,
+            Packet::ResumeFailed => 20,
+            Packet::Error { .. } => 21,
+            Packet::Disconnected => 22,
+            Packet::Rows { .. } => 23,
+        }
+    }
+
+    /// The capability which a client needs to be sent this packet, if any.
+    pub fn capability(&self) -> Option<Capabilities> {
+        match self {
+            Packet::Look { .. } | Packet::View { .. } => Some(Capabilities::LOOK),
+            Packet::GameOver { .. } => Some(Capabilities::STANDINGS),
+            Packet::CreateRoom
+            | Packet::JoinRoom { .. }
+            | Packet::Room { .. }
+            | Packet::RoomUnavailable { .. } => Some(Capabilities::ROOMS),
+            Packet::Resume { .. } | Packet::ResumeFailed => Some(Capabilities::RESUME),
+            Packet::Disconnected => Some(Capabilities::RECONNECT),
+            Packet::Rows { .. } => Some(Capabilities::PROGRESSIVE),
+            _ => None,
+        }
+    }
+}
+
+impl Encode for Coordinate {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_f32(self.latitude);
+        buf.put_f32(self.longitude);
+    }
+}
+
+impl Decode for Coordinate {
+    fn decode_with<B: Buf>(buf: &mut B, _: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            latitude: buf.try_get_f32()?,
+            longitude: buf.try_get_f32()?,
+        })
+    }
+}
+
+impl Encode for ClientOptions {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u8(self.color as u8);
+        let user: [u8; 16] = self.user.fixed();
+        buf.put_slice(&user);
+    }
+}
+
+impl Decode for ClientOptions {
+    fn decode_with<B: Buf>(buf: &mut B, _: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            color: Color::try_from(buf.try_get_u8()?)?,
+            user: fixed::<B, 16>(buf)?,
+        })
+    }
+}
+
+impl Encode for Display {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u16(self.width);
+        buf.put_u16(self.height);
+        buf.put_u8(self.depth as u8);
+        buf.put_u8(self.dither as u8);
+        buf.put_u32(self.max_payload);
+    }
+}
+
+impl Decode for Display {
+    fn decode_with<B: Buf>(buf: &mut B, _: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            width: buf.try_get_u16()?,
+            height: buf.try_get_u16()?,
+            depth: Depth::try_from(buf.try_get_u8()?)?,
+            dither: Dither::try_from(buf.try_get_u8()?)?,
+            max_payload: buf.try_get_u32()?,
+        })
+    }
+}
+
+impl Encode for lobby::Clients {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u32(self.len() as u32);
+        for item in self {
+            item.encode(buf);
+        }
+    }
+}
+
+impl Decode for lobby::Clients {
+    fn decode_with<B: Buf>(buf: &mut B, limits: Limits) -> Result<Self, Error> {
+        Ok(Self::from(list::<B, lobby::Client>(
+            buf,
+            "lobby",
+            limits.players,
+            limits,
+        )?))
+    }
+}
+
+impl Encode for lobby::Client {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u32(self.id as u32);
+        buf.put_u8(self.ready as u8);
+        self.options.encode(buf);
+    }
+}
+
+impl Decode for lobby::Client {
+    fn decode_with<B: Buf>(buf: &mut B, limits: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            id: buf.try_get_u32()? as usize,
+            ready: buf.try_get_u8()? != 0,
+            options: ClientOptions::decode_with(buf, limits)?,
+        })
+    }
+}
+
+impl Encode for RoundResult {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u32(self.number as u32);
+        self.answer.encode(buf);
+        buf.put_u32(self.players.len() as u32);
+        for item in &self.players {
+            item.encode(buf);
+        }
+    }
+}
+
+impl Decode for RoundResult {
+    fn decode_with<B: Buf>(buf: &mut B, limits: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            number: buf.try_get_u32()? as usize,
+            answer: Coordinate::decode_with(buf, limits)?,
+            players: list::<B, Player>(buf, "players", limits.players, limits)?,
+        })
+    }
+}
+
+impl Encode for Player {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u32(self.id as u32);
+        buf.put_u32(self.points);
+        buf.put_u32(self.delta);
+        buf.put_u8(self.guess.is_some() as u8);
+        match &self.guess {
+            Some(x) => x.encode(buf),
+            None => buf.put_bytes(0, 8),
+        }
+    }
+}
+
+impl Decode for Player {
+    fn decode_with<B: Buf>(buf: &mut B, limits: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            id: buf.try_get_u32()? as usize,
+            points: buf.try_get_u32()?,
+            delta: buf.try_get_u32()?,
+            guess: optional::<B, Coordinate>(buf, 8, limits)?,
+        })
+    }
+}
+
+impl Encode for GameResult {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u32(self.standings.len() as u32);
+        for item in &self.standings {
+            item.encode(buf);
+        }
+        buf.put_u8(self.best.is_some() as u8);
+        match &self.best {
+            Some(x) => x.encode(buf),
+            None => buf.put_bytes(0, 12),
+        }
+    }
+}
+
+impl Decode for GameResult {
+    fn decode_with<B: Buf>(buf: &mut B, limits: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            standings: list::<B, Standing>(buf, "standings", limits.players, limits)?,
+            best: optional::<B, BestGuess>(buf, 12, limits)?,
+        })
+    }
+}
+
+impl Encode for Standing {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u32(self.id as u32);
+        buf.put_u32(self.points);
+        buf.put_f32(self.distance);
+    }
+}
+
+impl Decode for Standing {
+    fn decode_with<B: Buf>(buf: &mut B, _: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            id: buf.try_get_u32()? as usize,
+            points: buf.try_get_u32()?,
+            distance: buf.try_get_f32()?,
+        })
+    }
+}
+
+impl Encode for BestGuess {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u32(self.id as u32);
+        buf.put_u32(self.round as u32);
+        buf.put_f32(self.distance);
+    }
+}
+
+impl Decode for BestGuess {
+    fn decode_with<B: Buf>(buf: &mut B, _: Limits) -> Result<Self, Error> {
+        Ok(Self {
+            id: buf.try_get_u32()? as usize,
+            round: buf.try_get_u32()? as usize,
+            distance: buf.try_get_f32()?,
+        })
+    }
+}
+
+impl Encode for Packet {
+    fn encode<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u8(self.tag());
+
+        match self {
+            Packet::Init {
+                capabilities,
+                options,
+                display,
+            } => {
+                buf.put_u16(PROTOCOL_VERSION);
+                buf.put_u32(capabilities.0);
+                options.encode(buf);
+                display.encode(buf);
+            }
+            Packet::Confirmed {
+                capabilities,
+                id,
+                token,
+                options,
+                lobby,
+            } => {
+                buf.put_u16(PROTOCOL_VERSION);
+                buf.put_u32(capabilities.0);
+                buf.put_u32(*id as u32);
+                buf.put_u64(*token);
+                options.encode(buf);
+                lobby.encode(buf);
+            }
+            Packet::LobbyEvent {
+                action,
+                user,
+                lobby,
+            } => {
+                buf.put_u8(*action as u8);
+                buf.put_u32(*user as u32);
+                lobby.encode(buf);
+            }
+            Packet::WaitingStatus { ready } => {
+                buf.put_u8(*ready as u8);
+            }
+            Packet::RoundLoading { lobby } => {
+                lobby.encode(buf);
+            }
+            Packet::Round {
+                number,
+                deadline,
+                width,
+                height,
+                image,
+            } => {
+                buf.put_u32(*number as u32);
+                buf.put_u32(*deadline);
+                buf.put_u16(*width);
+                buf.put_u16(*height);
+                buf.put_u32(image.len() as u32);
+                buf.put_slice(image);
+            }
+            Packet::Guess { coordinates } => {
+                coordinates.encode(buf);
+            }
+            Packet::Guessed { player } => {
+                buf.put_u32(*player as u32);
+            }
+            Packet::Result { results } => {
+                results.encode(buf);
+            }
+            Packet::RequestGameEnd => {}
+            Packet::SoftQuit => {}
+            Packet::Look { direction } => {
+                buf.put_u8(*direction as u8);
+            }
+            Packet::View {
+                direction,
+                width,
+                height,
+                image,
+            } => {
+                buf.put_u8(*direction as u8);
+                buf.put_u16(*width);
+                buf.put_u16(*height);
+                buf.put_u32(image.len() as u32);
+                buf.put_slice(image);
+            }
+            Packet::GameOver { results } => {
+                results.encode(buf);
+            }
+            Packet::CreateRoom => {}
+            Packet::JoinRoom { code } => {
+                let code: [u8; ROOM_CODE_LEN] = code.fixed();
+                buf.put_slice(&code);
+            }
+            Packet::Room { code, lobby } => {
+                let code: [u8; ROOM_CODE_LEN] = code.fixed();
+                buf.put_slice(&code);
+                lobby.encode(buf);
+            }
+            Packet::RoomUnavailable { code } => {
+                let code: [u8; ROOM_CODE_LEN] = code.fixed();
+                buf.put_slice(&code);
+            }
+            Packet::Resume { token } => {
+                buf.put_u64(*token);
+            }
+            Packet::ResumeFailed => {}
+            Packet::Error { reason, version } => {
+                buf.put_u8(*reason as u8);
+                buf.put_u16(*version);
+            }
+            Packet::Disconnected => {}
+            Packet::Rows {
+                direction,
+                first,
+                step,
+                image,
+            } => {
+                buf.put_u8(*direction as u8);
+                buf.put_u16(*first);
+                buf.put_u16(*step);
+                buf.put_u32(image.len() as u32);
+                buf.put_slice(image);
+            }
+        }
+    }
+}
+
+impl Decode for Packet {
+    fn decode_with<B: Buf>(buf: &mut B, limits: Limits) -> Result<Self, Error> {
+        match buf.try_get_u8()? {
+            1 => {
+                version(buf)?;
+                Ok(Self::Init {
+                    capabilities: Capabilities(buf.try_get_u32()?),
+                    options: ClientOptions::decode_with(buf, limits)?,
+                    display: Display::decode_with(buf, limits)?,
+                })
+            }
+            2 => {
+                version(buf)?;
+                Ok(Self::Confirmed {
+                    capabilities: Capabilities(buf.try_get_u32()?),
+                    id: buf.try_get_u32()? as usize,
+                    token: buf.try_get_u64()?,
+                    options: ClientOptions::decode_with(buf, limits)?,
+                    lobby: lobby::Clients::decode_with(buf, limits)?,
+                })
+            }
+            3 => Ok(Self::LobbyEvent {
+                action: Action::try_from(buf.try_get_u8()?)?,
+                user: buf.try_get_u32()? as usize,
+                lobby: lobby::Clients::decode_with(buf, limits)?,
+            }),
+            4 => Ok(Self::WaitingStatus {
+                ready: buf.try_get_u8()? != 0,
+            }),
+            5 => Ok(Self::RoundLoading {
+                lobby: lobby::Clients::decode_with(buf, limits)?,
+            }),
+            6 => Ok(Self::Round {
+                number: buf.try_get_u32()? as usize,
+                deadline: buf.try_get_u32()?,
+                width: buf.try_get_u16()?,
+                height: buf.try_get_u16()?,
+                image: bytes(buf, "image", limits.image)?,
+            }),
+            7 => Ok(Self::Guess {
+                coordinates: Coordinate::decode_with(buf, limits)?,
+            }),
+            8 => Ok(Self::Guessed {
+                player: buf.try_get_u32()? as usize,
+            }),
+            9 => Ok(Self::Result {
+                results: RoundResult::decode_with(buf, limits)?,
+            }),
+            10 => Ok(Self::RequestGameEnd),
+            11 => Ok(Self::SoftQuit),
+            12 => Ok(Self::Look {
+                direction: Direction::try_from(buf.try_get_u8()?)?,
+            }),
+            13 => Ok(Self::View {
+                direction: Direction::try_from(buf.try_get_u8()?)?,
+                width: buf.try_get_u16()?,
+                height: buf.try_get_u16()?,
+                image: bytes(buf, "image", limits.image)?,
+            }),
+            14 => Ok(Self::GameOver {
+                results: GameResult::decode_with(buf, limits)?,
+            }),
+            15 => Ok(Self::CreateRoom),
+            16 => Ok(Self::JoinRoom {
+                code: fixed::<B, ROOM_CODE_LEN>(buf)?,
+            }),
+            17 => Ok(Self::Room {
+                code: fixed::<B, ROOM_CODE_LEN>(buf)?,
+                lobby: lobby::Clients::decode_with(buf, limits)?,
+            }),
+            18 => Ok(Self::RoomUnavailable {
+                code: fixed::<B, ROOM_CODE_LEN>(buf)?,
+            }),
+            19 => Ok(Self::Resume {
+                token: buf.try_get_u64()?,
+            }),
+            20 => Ok(Self::ResumeFailed),
+            21 => Ok(Self::Error {
+                reason: Reason::try_from(buf.try_get_u8()?)?,
+                version: buf.try_get_u16()?,
+            }),
+            22 => Ok(Self::Disconnected),
+            23 => Ok(Self::Rows {
+                direction: Direction::try_from(buf.try_get_u8()?)?,
+                first: buf.try_get_u16()?,
+                step: buf.try_get_u16()?,
+                image: bytes(buf, "image", limits.image)?,
+            }),
+            tag => Err(Error::Unknown(tag)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod io {
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    use super::*;
+    use crate::{
+        deserializers::{Deserialize, bytes, fixed, list, optional, version},
+        serializers::Serialize,
+    };
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for Coordinate {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_f32(self.latitude).await?;
+            writer.write_f32(self.longitude).await?;
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for Coordinate {
+        async fn deserialize_with(reader: &mut R, _: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                latitude: reader.read_f32().await?,
+                longitude: reader.read_f32().await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for ClientOptions {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u8(self.color as u8).await?;
+            let user: [u8; 16] = self.user.fixed();
+            writer.write_all(&user).await?;
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for ClientOptions {
+        async fn deserialize_with(reader: &mut R, _: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                color: Color::try_from(reader.read_u8().await?)?,
+                user: fixed::<R, 16>(reader).await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for Display {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u16(self.width).await?;
+            writer.write_u16(self.height).await?;
+            writer.write_u8(self.depth as u8).await?;
+            writer.write_u8(self.dither as u8).await?;
+            writer.write_u32(self.max_payload).await?;
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for Display {
+        async fn deserialize_with(reader: &mut R, _: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                width: reader.read_u16().await?,
+                height: reader.read_u16().await?,
+                depth: Depth::try_from(reader.read_u8().await?)?,
+                dither: Dither::try_from(reader.read_u8().await?)?,
+                max_payload: reader.read_u32().await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for lobby::Clients {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u32(self.len() as u32).await?;
+            for item in self {
+                item.serialize(writer).await?;
+            }
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for lobby::Clients {
+        async fn deserialize_with(reader: &mut R, limits: Limits) -> Result<Self, Error> {
+            Ok(Self::from(
+                list::<R, lobby::Client>(reader, "lobby", limits.players, limits).await?,
+            ))
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for lobby::Client {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u32(self.id as u32).await?;
+            writer.write_u8(self.ready as u8).await?;
+            self.options.serialize(writer).await?;
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for lobby::Client {
+        async fn deserialize_with(reader: &mut R, limits: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                id: reader.read_u32().await? as usize,
+                ready: reader.read_u8().await? != 0,
+                options: ClientOptions::deserialize_with(reader, limits).await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for RoundResult {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u32(self.number as u32).await?;
+            self.answer.serialize(writer).await?;
+            writer.write_u32(self.players.len() as u32).await?;
+            for item in &self.players {
+                item.serialize(writer).await?;
+            }
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for RoundResult {
+        async fn deserialize_with(reader: &mut R, limits: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                number: reader.read_u32().await? as usize,
+                answer: Coordinate::deserialize_with(reader, limits).await?,
+                players: list::<R, Player>(reader, "players", limits.players, limits).await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for Player {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u32(self.id as u32).await?;
+            writer.write_u32(self.points).await?;
+            writer.write_u32(self.delta).await?;
+            writer.write_u8(self.guess.is_some() as u8).await?;
+            match &self.guess {
+                Some(x) => x.serialize(writer).await?,
+                None => writer.write_all(&[0; 8]).await?,
+            }
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for Player {
+        async fn deserialize_with(reader: &mut R, limits: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                id: reader.read_u32().await? as usize,
+                points: reader.read_u32().await?,
+                delta: reader.read_u32().await?,
+                guess: optional::<R, Coordinate>(reader, 8, limits).await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for GameResult {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u32(self.standings.len() as u32).await?;
+            for item in &self.standings {
+                item.serialize(writer).await?;
+            }
+            writer.write_u8(self.best.is_some() as u8).await?;
+            match &self.best {
+                Some(x) => x.serialize(writer).await?,
+                None => writer.write_all(&[0; 12]).await?,
+            }
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for GameResult {
+        async fn deserialize_with(reader: &mut R, limits: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                standings: list::<R, Standing>(reader, "standings", limits.players, limits).await?,
+                best: optional::<R, BestGuess>(reader, 12, limits).await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for Standing {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u32(self.id as u32).await?;
+            writer.write_u32(self.points).await?;
+            writer.write_f32(self.distance).await?;
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for Standing {
+        async fn deserialize_with(reader: &mut R, _: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                id: reader.read_u32().await? as usize,
+                points: reader.read_u32().await?,
+                distance: reader.read_f32().await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for BestGuess {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u32(self.id as u32).await?;
+            writer.write_u32(self.round as u32).await?;
+            writer.write_f32(self.distance).await?;
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for BestGuess {
+        async fn deserialize_with(reader: &mut R, _: Limits) -> Result<Self, Error> {
+            Ok(Self {
+                id: reader.read_u32().await? as usize,
+                round: reader.read_u32().await? as usize,
+                distance: reader.read_f32().await?,
+            })
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin + Send> Serialize<W> for Packet {
+        async fn serialize(&self, writer: &mut W) -> Result<(), Error> {
+            writer.write_u8(self.tag()).await?;
+
+            match self {
+                Packet::Init {
+                    capabilities,
+                    options,
+                    display,
+                } => {
+                    writer.write_u16(PROTOCOL_VERSION).await?;
+                    writer.write_u32(capabilities.0).await?;
+                    options.serialize(writer).await?;
+                    display.serialize(writer).await?;
+                }
+                Packet::Confirmed {
+                    capabilities,
+                    id,
+                    token,
+                    options,
+                    lobby,
+                } => {
+                    writer.write_u16(PROTOCOL_VERSION).await?;
+                    writer.write_u32(capabilities.0).await?;
+                    writer.write_u32(*id as u32).await?;
+                    writer.write_u64(*token).await?;
+                    options.serialize(writer).await?;
+                    lobby.serialize(writer).await?;
+                }
+                Packet::LobbyEvent {
+                    action,
+                    user,
+                    lobby,
+                } => {
+                    writer.write_u8(*action as u8).await?;
+                    writer.write_u32(*user as u32).await?;
+                    lobby.serialize(writer).await?;
+                }
+                Packet::WaitingStatus { ready } => {
+                    writer.write_u8(*ready as u8).await?;
+                }
+                Packet::RoundLoading { lobby } => {
+                    lobby.serialize(writer).await?;
+                }
+                Packet::Round {
+                    number,
+                    deadline,
+                    width,
+                    height,
+                    image,
+                } => {
+                    writer.write_u32(*number as u32).await?;
+                    writer.write_u32(*deadline).await?;
+                    writer.write_u16(*width).await?;
+                    writer.write_u16(*height).await?;
+                    writer.write_u32(image.len() as u32).await?;
+                    writer.write_all(image).await?;
+                }
+                Packet::Guess { coordinates } => {
+                    coordinates.serialize(writer).await?;
+                }
+                Packet::Guessed { player } => {
+                    writer.write_u32(*player as u32).await?;
+                }
+                Packet::Result { results } => {
+                    results.serialize(writer).await?;
+                }
+                Packet::RequestGameEnd => {}
+                Packet::SoftQuit => {}
+                Packet::Look { direction } => {
+                    writer.write_u8(*direction as u8).await?;
+                }
+                Packet::View {
+                    direction,
+                    width,
+                    height,
+                    image,
+                } => {
+                    writer.write_u8(*direction as u8).await?;
+                    writer.write_u16(*width).await?;
+                    writer.write_u16(*height).await?;
+                    writer.write_u32(image.len() as u32).await?;
+                    writer.write_all(image).await?;
+                }
+                Packet::GameOver { results } => {
+                    results.serialize(writer).await?;
+                }
+                Packet::CreateRoom => {}
+                Packet::JoinRoom { code } => {
+                    let code: [u8; ROOM_CODE_LEN] = code.fixed();
+                    writer.write_all(&code).await?;
+                }
+                Packet::Room { code, lobby } => {
+                    let code: [u8; ROOM_CODE_LEN] = code.fixed();
+                    writer.write_all(&code).await?;
+                    lobby.serialize(writer).await?;
+                }
+                Packet::RoomUnavailable { code } => {
+                    let code: [u8; ROOM_CODE_LEN] = code.fixed();
+                    writer.write_all(&code).await?;
+                }
+                Packet::Resume { token } => {
+                    writer.write_u64(*token).await?;
+                }
+                Packet::ResumeFailed => {}
+                Packet::Error { reason, version } => {
+                    writer.write_u8(*reason as u8).await?;
+                    writer.write_u16(*version).await?;
+                }
+                Packet::Disconnected => {}
+                Packet::Rows {
+                    direction,
+                    first,
+                    step,
+                    image,
+                } => {
+                    writer.write_u8(*direction as u8).await?;
+                    writer.write_u16(*first).await?;
+                    writer.write_u16(*step).await?;
+                    writer.write_u32(image.len() as u32).await?;
+                    writer.write_all(image).await?;
+                }
+            }
+
+            writer.flush().await?;
+            Ok(())
+        }
+    }
+
+    impl<R: AsyncRead + Unpin + Send> Deserialize<R> for Packet {
+        async fn deserialize_with(reader: &mut R, limits: Limits) -> Result<Self, Error> {
+            match reader.read_u8().await? {
+                1 => {
+                    version(reader).await?;
+                    Ok(Self::Init {
+                        capabilities: Capabilities(reader.read_u32().await?),
+                        options: ClientOptions::deserialize_with(reader, limits).await?,
+                        display: Display::deserialize_with(reader, limits).await?,
+                    })
+                }
+                2 => {
+                    version(reader).await?;
+                    Ok(Self::Confirmed {
+                        capabilities: Capabilities(reader.read_u32().await?),
+                        id: reader.read_u32().await? as usize,
+                        token: reader.read_u64().await?,
+                        options: ClientOptions::deserialize_with(reader, limits).await?,
+                        lobby: lobby::Clients::deserialize_with(reader, limits).await?,
+                    })
+                }
+                3 => Ok(Self::LobbyEvent {
+                    action: Action::try_from(reader.read_u8().await?)?,
+                    user: reader.read_u32().await? as usize,
+                    lobby: lobby::Clients::deserialize_with(reader, limits).await?,
+                }),
+                4 => Ok(Self::WaitingStatus {
+                    ready: reader.read_u8().await? != 0,
+                }),
+                5 => Ok(Self::RoundLoading {
+                    lobby: lobby::Clients::deserialize_with(reader, limits).await?,
+                }),
+                6 => Ok(Self::Round {
+                    number: reader.read_u32().await? as usize,
+                    deadline: reader.read_u32().await?,
+                    width: reader.read_u16().await?,
+                    height: reader.read_u16().await?,
+                    image: bytes(reader, "image", limits.image).await?,
+                }),
+                7 => Ok(Self::Guess {
+                    coordinates: Coordinate::deserialize_with(reader, limits).await?,
+                }),
+                8 => Ok(Self::Guessed {
+                    player: reader.read_u32().await? as usize,
+                }),
+                9 => Ok(Self::Result {
+                    results: RoundResult::deserialize_with(reader, limits).await?,
+                }),
+                10 => Ok(Self::RequestGameEnd),
+                11 => Ok(Self::SoftQuit),
+                12 => Ok(Self::Look {
+                    direction: Direction::try_from(reader.read_u8().await?)?,
+                }),
+                13 => Ok(Self::View {
+                    direction: Direction::try_from(reader.read_u8().await?)?,
+                    width: reader.read_u16().await?,
+                    height: reader.read_u16().await?,
+                    image: bytes(reader, "image", limits.image).await?,
+                }),
+                14 => Ok(Self::GameOver {
+                    results: GameResult::deserialize_with(reader, limits).await?,
+                }),
+                15 => Ok(Self::CreateRoom),
+                16 => Ok(Self::JoinRoom {
+                    code: fixed::<R, ROOM_CODE_LEN>(reader).await?,
+                }),
+                17 => Ok(Self::Room {
+                    code: fixed::<R, ROOM_CODE_LEN>(reader).await?,
+                    lobby: lobby::Clients::deserialize_with(reader, limits).await?,
+                }),
+                18 => Ok(Self::RoomUnavailable {
+                    code: fixed::<R, ROOM_CODE_LEN>(reader).await?,
+                }),
+                19 => Ok(Self::Resume {
+                    token: reader.read_u64().await?,
+                }),
+                20 => Ok(Self::ResumeFailed),
+                21 => Ok(Self::Error {
+                    reason: Reason::try_from(reader.read_u8().await?)?,
+                    version: reader.read_u16().await?,
+                }),
+                22 => Ok(Self::Disconnected),
+                23 => Ok(Self::Rows {
+                    direction: Direction::try_from(reader.read_u8().await?)?,
+                    first: reader.read_u16().await?,
+                    step: reader.read_u16().await?,
+                    image: bytes(reader, "image", limits.image).await?,
+                }),
+                tag => Err(Error::Unknown(tag)),
+            }
+        }
+    }
+}
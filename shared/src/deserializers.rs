@@ -1,146 +1,98 @@
-use crate::{ClientOptions, Color, Coordinate, Error, Packet, Player, RoundResult, lobby};
+use crate::{Error, Limits, PROTOCOL_VERSION};
 use bytes::Bytes;
 use tokio::io::{AsyncRead, AsyncReadExt};
 
 pub trait Deserialize<R: AsyncRead + Unpin + Send>: Sized {
-    fn deserialize(reader: &mut R)
-    -> impl std::future::Future<Output = Result<Self, Error>> + Send;
-}
-
-impl<R: AsyncRead + Unpin + Send> Deserialize<R> for ClientOptions {
-    async fn deserialize(reader: &mut R) -> Result<Self, Error> {
-        let color: Color = unsafe { std::mem::transmute(reader.read_u8().await?) };
-
-        let mut user = [0; 16];
-        reader.read_exact(&mut user).await?;
-        let user = std::str::from_utf8(&user)?
-            .trim_end_matches('\0')
-            .to_owned();
-
-        Ok(Self { color, user })
+    /// Reads a value, failing if any variable length field inside of it goes over the limits.
+    fn deserialize_with(
+        reader: &mut R,
+        limits: Limits,
+    ) -> impl std::future::Future<Output = Result<Self, Error>> + Send;
+
+    fn deserialize(
+        reader: &mut R,
+    ) -> impl std::future::Future<Output = Result<Self, Error>> + Send {
+        Self::deserialize_with(reader, Limits::default())
     }
 }
 
-impl<R: AsyncRead + Unpin + Send> Deserialize<R> for lobby::Client {
-    async fn deserialize(reader: &mut R) -> Result<Self, Error> {
-        let id = reader.read_u32().await? as usize;
-        let ready = reader.read_u8().await? != 0;
-        let options = ClientOptions::deserialize(reader).await?;
-        Ok(crate::lobby::Client { id, ready, options })
+/// Reads a length, making sure that it's not over the maximum.
+pub(crate) async fn len<R: AsyncRead + Unpin + Send>(
+    reader: &mut R,
+    field: &'static str,
+    max: usize,
+) -> Result<usize, Error> {
+    let len = reader.read_u32().await? as usize;
+    if len > max {
+        return Err(Error::TooLong(field, len, max));
     }
-}
 
-impl<R: AsyncRead + Unpin + Send> Deserialize<R> for Coordinate {
-    async fn deserialize(reader: &mut R) -> Result<Self, Error> {
-        let latitude = reader.read_f32().await?;
-        let longitude = reader.read_f32().await?;
-        Ok(Self {
-            latitude,
-            longitude,
-        })
-    }
+    Ok(len)
 }
 
-impl<R: AsyncRead + Unpin + Send> Deserialize<R> for Player {
-    async fn deserialize(reader: &mut R) -> Result<Self, Error> {
-        let id = reader.read_u32().await? as usize;
-        let points = reader.read_u32().await?;
-        let delta = reader.read_u32().await?;
-        let has_guess = reader.read_u8().await? != 0;
-        let guess = if has_guess {
-            Some(Coordinate::deserialize(reader).await?)
-        } else {
-            let mut pad = [0u8; 2];
-            reader.read_exact(&mut pad).await?;
-            None
-        };
+/// Reads a fixed length, null padded string.
+pub(crate) async fn fixed<R: AsyncRead + Unpin + Send, const LEN: usize>(
+    reader: &mut R,
+) -> Result<String, Error> {
+    let mut buf = [0; LEN];
+    reader.read_exact(&mut buf).await?;
+    Ok(std::str::from_utf8(&buf)?.trim_end_matches('\0').to_owned())
+}
 
-        Ok(Self {
-            id,
-            points,
-            delta,
-            guess,
-        })
+/// Checks the protocol version at the start of a handshake.
+///
+/// If the version doesn't match, nothing else is read, since the rest of
+/// the packet might not even be laid out the same way.
+pub(crate) async fn version<R: AsyncRead + Unpin + Send>(reader: &mut R) -> Result<(), Error> {
+    let version = reader.read_u16().await?;
+    if version != PROTOCOL_VERSION {
+        return Err(Error::Version(version));
     }
+
+    Ok(())
 }
 
-impl<R: AsyncRead + Unpin + Send> Deserialize<R> for RoundResult {
-    async fn deserialize(reader: &mut R) -> Result<Self, Error> {
-        let number = reader.read_u32().await? as usize;
-        let answer = Coordinate::deserialize(reader).await?;
+/// Reads a length prefixed blob of bytes.
+pub(crate) async fn bytes<R: AsyncRead + Unpin + Send>(
+    reader: &mut R,
+    field: &'static str,
+    max: usize,
+) -> Result<Bytes, Error> {
+    let len = len(reader, field, max).await?;
 
-        let len = reader.read_u32().await? as usize;
-        let mut players = Vec::with_capacity(len);
-        for _ in 0..len {
-            players.push(Player::deserialize(reader).await?);
-        }
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf).await?;
 
-        Ok(RoundResult {
-            number,
-            answer,
-            players,
-        })
-    }
+    Ok(Bytes::from(buf))
 }
 
-impl<R: AsyncRead + Unpin + Send> Deserialize<R> for lobby::Clients {
-    async fn deserialize(reader: &mut R) -> Result<Self, Error> {
-        let len = reader.read_u32().await? as usize;
-        let mut clients = Vec::with_capacity(len);
-        for _ in 0..len {
-            clients.push(lobby::Client::deserialize(reader).await?);
-        }
-
-        Ok(Self::from(clients))
+/// Reads a length prefixed list of values.
+pub(crate) async fn list<R: AsyncRead + Unpin + Send, T: Deserialize<R>>(
+    reader: &mut R,
+    field: &'static str,
+    max: usize,
+    limits: Limits,
+) -> Result<Vec<T>, Error> {
+    let len = len(reader, field, max).await?;
+    let mut list = Vec::with_capacity(len);
+    for _ in 0..len {
+        list.push(T::deserialize_with(reader, limits).await?);
     }
-}
 
-impl<R: AsyncRead + Unpin + Send> Deserialize<R> for Packet {
-    async fn deserialize(reader: &mut R) -> Result<Self, Error> {
-        match reader.read_u8().await? {
-            1 => Ok(Self::Init {
-                options: ClientOptions::deserialize(reader).await?,
-            }),
-            2 => Ok(Self::Confirmed {
-                id: reader.read_u32().await? as usize,
-                options: ClientOptions::deserialize(reader).await?,
-                lobby: lobby::Clients::deserialize(reader).await?,
-            }),
-            3 => Ok(Self::LobbyEvent {
-                action: unsafe { std::mem::transmute(reader.read_u8().await?) },
-                user: reader.read_u32().await? as usize,
-                lobby: lobby::Clients::deserialize(reader).await?,
-            }),
-            4 => Ok(Self::WaitingStatus {
-                ready: reader.read_u8().await? != 0,
-            }),
-            5 => Ok(Self::RoundLoading {
-                lobby: lobby::Clients::deserialize(reader).await?,
-            }),
-            6 => {
-                let number = reader.read_u32().await? as usize;
-                let len = reader.read_u32().await? as usize;
-
-                let mut buf = vec![0u8; len];
-                reader.read_exact(&mut buf).await?;
+    Ok(list)
+}
 
-                Ok(Self::Round {
-                    number,
-                    image: Bytes::from(buf),
-                })
-            }
-            7 => Ok(Self::Guess {
-                coordinates: Coordinate::deserialize(reader).await?,
-            }),
-            8 => Ok(Self::Guessed {
-                player: reader.read_u32().await? as usize,
-            }),
-            9 => Ok(Self::Result {
-                results: RoundResult::deserialize(reader).await?,
-            }),
-            10 => Ok(Self::RequestGameEnd),
-            11 => Ok(Self::SoftQuit),
-            tag => Err(Error::Unknown(tag)),
-        }
+/// Reads a flag followed by a value, which is just `size` bytes of padding if the flag isn't set.
+pub(crate) async fn optional<R: AsyncRead + Unpin + Send, T: Deserialize<R>>(
+    reader: &mut R,
+    size: usize,
+    limits: Limits,
+) -> Result<Option<T>, Error> {
+    if reader.read_u8().await? != 0 {
+        return Ok(Some(T::deserialize_with(reader, limits).await?));
     }
+
+    let mut pad = vec![0u8; size];
+    reader.read_exact(&mut pad).await?;
+    Ok(None)
 }
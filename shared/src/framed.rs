@@ -0,0 +1,113 @@
+//! A [`tokio_util::codec`] for packets, so that they can be read as a [`Stream`] and written
+//! as a [`Sink`] with [`Framed`] over anything which is [`AsyncRead`] or [`AsyncWrite`],
+//! like TCP sockets, serial ports, pipes or in-memory duplex streams.
+//!
+//! Unlike [`PacketReadExt::read_packet`], reading from a [`FramedRead`] is cancel safe,
+//! since bytes of a packet which is only partially there are kept in its buffer.
+//!
+//! [`PacketCodec`] speaks the raw protocol, and [`FrameCodec`] wraps packets
+//! in the checksummed frames from [`serial`](crate::serial) instead.
+//!
+//! [`Stream`]: https://docs.rs/futures/latest/futures/stream/trait.Stream.html
+//! [`Sink`]: https://docs.rs/futures/latest/futures/sink/trait.Sink.html
+//! [`Framed`]: tokio_util::codec::Framed
+//! [`FramedRead`]: tokio_util::codec::FramedRead
+//! [`AsyncRead`]: tokio::io::AsyncRead
+//! [`AsyncWrite`]: tokio::io::AsyncWrite
+//! [`PacketReadExt::read_packet`]: crate::PacketReadExt::read_packet
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    Error, Limits, Packet, serial,
+    sync::{Decode, Encode},
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PacketCodec {
+    pub limits: Limits,
+}
+
+impl PacketCodec {
+    pub fn new(limits: Limits) -> Self {
+        Self { limits }
+    }
+}
+
+impl Decoder for PacketCodec {
+    type Item = Packet;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+
+        // Packets aren't length prefixed, so the only way to know if all of one is
+        // there is to try reading it, and start over once more bytes come in.
+        let mut reader = &src[..];
+        match Packet::decode_with(&mut reader, self.limits) {
+            Ok(packet) => {
+                let len = src.len() - reader.len();
+                src.advance(len);
+                Ok(Some(packet))
+            }
+            Err(Error::Incomplete) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl Encoder<Packet> for PacketCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        item.encode(dst);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameCodec {
+    pub limits: Limits,
+}
+
+impl FrameCodec {
+    pub fn new(limits: Limits) -> Self {
+        Self { limits }
+    }
+}
+
+impl Decoder for FrameCodec {
+    /// Corrupted frames come back as errors within the stream, instead of ending it,
+    /// since the next frame after one can still be read just fine.
+    type Item = Result<Packet, Error>;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match serial::decode(src, self.limits) {
+            Ok(packet) => Ok(packet.map(Ok)),
+            Err(error) => Ok(Some(Err(error))),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        // Whatever's left of a frame that got cut off when the other side went away is dropped.
+        let frame = self.decode(src)?;
+        if frame.is_none() {
+            src.clear();
+        }
+
+        Ok(frame)
+    }
+}
+
+impl Encoder<Packet> for FrameCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        serial::encode(&item, dst);
+        Ok(())
+    }
+}
@@ -1,53 +1,427 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut};
-use image::{Pixel, Rgb, RgbImage};
+#[cfg(feature = "std")]
+use bytes::{BufMut, Bytes, BytesMut};
+#[cfg(feature = "std")]
+use color_quant::NeuQuant;
+#[cfg(feature = "std")]
+use image::{Rgb, RgbImage, imageops};
+
+#[cfg(feature = "std")]
+use crate::Dither;
+use crate::{Depth, Display};
 
 pub const WIDTH: u32 = 320;
 pub const HEIGHT: u32 = 240;
 pub const SIZE: u32 = 320 * 240;
 
-pub fn encode(image: RgbImage) -> std::io::Result<Bytes> {
-    let mut bytes = BytesMut::with_capacity(SIZE as usize);
+/// How many colors a [`Depth::Indexed`] image's palette has.
+pub const PALETTE: u32 = 256;
 
-    let (width, height) = image.dimensions();
-    assert!(width % WIDTH == 0, "width is incorrect!");
-    assert!(height % HEIGHT == 0, "height is incorrect!");
+/// Why an image couldn't be encoded or decoded.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum Error {
+    #[error("invalid image dimensions: {0}x{1}")]
+    Dimensions(u32, u32),
+
+    #[error("image size mismatch: expected {0} bytes, got {1}")]
+    Size(usize, usize),
+
+    #[error("image ends in the middle of a run")]
+    Truncated,
+}
+
+/// How many bytes an image of `width` by `height` pixels takes up, as long as it has any pixels
+/// and both sides fit in the `u16` that packets carry them in.
+pub fn len(width: u32, height: u32, depth: Depth) -> Result<usize, Error> {
+    let fits = |side: u32| (1..=u16::MAX as u32).contains(&side);
+    if !fits(width) || !fits(height) {
+        return Err(Error::Dimensions(width, height));
+    }
+
+    let len = width as u64 * height as u64 * depth.bytes() as u64 + depth.palette() as u64;
+    usize::try_from(len).map_err(|_| Error::Dimensions(width, height))
+}
+
+impl Depth {
+    /// How many bytes each pixel takes up.
+    pub fn bytes(self) -> u32 {
+        match self {
+            Depth::Rgb332 | Depth::Indexed => 1,
+            Depth::Rgb888 => 3,
+        }
+    }
 
-    for pixel in image.pixels() {
-        let [r, g, b] = pixel.channels() else {
-            panic!("channels don't match up!")
+    /// How many bytes an image of `width` by `height` pixels takes up, palette and all.
+    pub fn size(self, width: u32, height: u32) -> u32 {
+        self.palette() + width * height * self.bytes()
+    }
+
+    /// How many bytes of palette come before the pixels.
+    pub fn palette(self) -> u32 {
+        match self {
+            Depth::Indexed => PALETTE * 3,
+            Depth::Rgb332 | Depth::Rgb888 => 0,
+        }
+    }
+}
+
+impl Display {
+    /// The size which a full [`WIDTH`] by [`HEIGHT`] image gets scaled down to, keeping its
+    /// aspect ratio, so that it fits on the display and within the maximum payload.
+    ///
+    /// Images are sent along with their size anyway, but this is what clients can expect.
+    pub fn dimensions(&self) -> (u32, u32) {
+        let fits = |(width, height): &(u32, u32)| {
+            *width <= self.width.max(1) as u32
+                && *height <= self.height.max(1) as u32
+                && (self.max_payload == 0 || self.depth.size(*width, *height) <= self.max_payload)
         };
 
-        let r = (*r >> 5) & 0x07;
-        let g = (*g >> 5) & 0x07;
-        let b = (*b >> 6) & 0x03;
+        (1..=WIDTH)
+            .rev()
+            .map(|width| (width, (width * HEIGHT / WIDTH).max(1)))
+            .find(fits)
+            .unwrap_or((1, 1))
+    }
 
-        let combined = (r << 5) | (g << 2) | b;
-        bytes.put_u8(combined);
+    /// How many bytes an image fitted to the display takes up.
+    pub fn size(&self) -> u32 {
+        let (width, height) = self.dimensions();
+        self.depth.size(width, height)
+    }
+}
+
+/// Encodes an image how a client wants it, approximating the colors that the depth can't show
+/// with `dither`. [`Depth::Indexed`] images get a palette picked just for them.
+#[cfg(feature = "std")]
+pub fn encode(image: &RgbImage, depth: Depth, dither: Dither) -> Result<Bytes, Error> {
+    let (width, height) = image.dimensions();
+    let mut bytes = BytesMut::with_capacity(len(width, height, depth)?);
+
+    match depth {
+        Depth::Rgb332 => quantize(image, &Palette::Rgb332, dither, &mut bytes),
+        Depth::Rgb888 => bytes.put_slice(image.as_raw()),
+        Depth::Indexed => {
+            let palette = Palette::new(image);
+            for index in 0..PALETTE {
+                bytes.put_slice(&palette.color(index as u8));
+            }
+            quantize(image, &palette, dither, &mut bytes);
+        }
     }
 
     Ok(bytes.freeze())
 }
 
-pub fn decode(mut bytes: BytesMut, width: u32, height: u32) -> std::io::Result<RgbImage> {
-    assert!(width % WIDTH == 0, "width is incorrect!");
-    assert!(height % HEIGHT == 0, "height is incorrect!");
+/// Scales a full size image down to fit a client's display, and encodes it how it wants.
+#[cfg(feature = "std")]
+pub fn fit(image: &RgbImage, display: &Display) -> Result<Bytes, Error> {
+    let (width, height) = display.dimensions();
+    if image.dimensions() == (width, height) {
+        return encode(image, display.depth, display.dither);
+    }
+
+    let resized = imageops::resize(image, width, height, imageops::FilterType::Triangle);
+    encode(&resized, display.depth, display.dither)
+}
+
+/// The colors which pixels that are a single byte can be.
+#[cfg(feature = "std")]
+enum Palette {
+    /// The same colors for every image, with each channel in its own bits.
+    Rgb332,
+
+    /// Every color of an image which doesn't have more than fit in a palette.
+    Exact(Vec<[u8; 3]>),
+
+    /// Colors picked for one image in particular.
+    Adaptive(NeuQuant),
+}
+
+#[cfg(feature = "std")]
+impl Palette {
+    /// Picks the colors for an image's palette.
+    fn new(image: &RgbImage) -> Self {
+        let mut colors = Vec::new();
+        for Rgb(pixel) in image.pixels() {
+            if colors.contains(pixel) {
+                continue;
+            }
+
+            if colors.len() == PALETTE as usize {
+                let pixels: Vec<u8> = image
+                    .pixels()
+                    .flat_map(|Rgb([r, g, b])| [*r, *g, *b, 0xff])
+                    .collect();
+                return Palette::Adaptive(NeuQuant::new(10, PALETTE as usize, &pixels));
+            }
+
+            colors.push(*pixel);
+        }
+
+        Palette::Exact(colors)
+    }
+
+    fn index(&self, [r, g, b]: [u8; 3]) -> u8 {
+        match self {
+            // Rounded to the closest level, rather than cut off, so nothing gets darker.
+            Palette::Rgb332 => {
+                let level = |channel: u8, max: u16| ((channel as u16 * max + 127) / 255) as u8;
+                (level(r, 7) << 5) | (level(g, 7) << 2) | level(b, 3)
+            }
+            Palette::Exact(colors) => {
+                let distance = |color: &[u8; 3]| {
+                    let [dr, dg, db] = [
+                        color[0].abs_diff(r),
+                        color[1].abs_diff(g),
+                        color[2].abs_diff(b),
+                    ]
+                    .map(|x| x as u32 * x as u32);
+                    dr + dg + db
+                };
+                (0..colors.len())
+                    .min_by_key(|i| distance(&colors[*i]))
+                    .unwrap_or_default() as u8
+            }
+            Palette::Adaptive(quant) => quant.index_of(&[r, g, b, 0xff]) as u8,
+        }
+    }
+
+    fn color(&self, index: u8) -> [u8; 3] {
+        match self {
+            Palette::Rgb332 => rgb332(index),
+            Palette::Exact(colors) => colors.get(index as usize).copied().unwrap_or_default(),
+            Palette::Adaptive(quant) => {
+                let [r, g, b, _] = quant.lookup(index as usize).unwrap_or_default();
+                [r, g, b]
+            }
+        }
+    }
+
+    /// About how far apart neighbouring colors are in each channel, which is as far as
+    /// ordered dithering nudges a pixel either way.
+    fn spread(&self) -> [i32; 3] {
+        match self {
+            Palette::Rgb332 => [255 / 7, 255 / 7, 255 / 3],
+            Palette::Exact(_) | Palette::Adaptive(_) => [24, 24, 24],
+        }
+    }
+}
+
+/// Thresholds for ordered dithering, which are repeated over the whole image.
+#[cfg(feature = "std")]
+const BAYER: [[i32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Maps every pixel of an image to the index of a color in `palette`.
+#[cfg(feature = "std")]
+fn quantize(image: &RgbImage, palette: &Palette, dither: Dither, bytes: &mut BytesMut) {
+    let (width, height) = image.dimensions();
+    let spread = palette.spread();
 
-    let mut img = RgbImage::new(width, height);
+    // What Floyd–Steinberg carries over onto this row and the next, in sixteenths, with a
+    // pixel of padding on either side so that the edges don't need to be special cased.
+    let mut row = vec![[0i32; 3]; width as usize + 2];
+    let mut next = row.clone();
 
     for y in 0..height {
         for x in 0..width {
-            let combined = bytes.get_u8();
-            let r = (combined >> 5) & 0x07;
-            let g = (combined >> 2) & 0x07;
-            let b = combined & 0x03;
+            let Rgb(pixel) = *image.get_pixel(x, y);
+            let i = x as usize + 1;
+
+            let target: [u8; 3] = std::array::from_fn(|c| {
+                let offset = match dither {
+                    Dither::None => 0,
+                    Dither::FloydSteinberg => row[i][c] / 16,
+                    Dither::Ordered => {
+                        (BAYER[y as usize % 4][x as usize % 4] * 2 - 15) * spread[c] / 32
+                    }
+                };
+                (pixel[c] as i32 + offset).clamp(0, 255) as u8
+            });
 
-            let r = (r as u16 * 255 / 7) as u8;
-            let g = (g as u16 * 255 / 7) as u8;
-            let b = (b as u16 * 255 / 3) as u8;
+            let index = palette.index(target);
+            bytes.put_u8(index);
 
-            img.put_pixel(x, y, Rgb([r, g, b]));
+            if dither == Dither::FloydSteinberg {
+                let color = palette.color(index);
+                for c in 0..3 {
+                    let error = target[c] as i32 - color[c] as i32;
+                    row[i + 1][c] += error * 7;
+                    next[i - 1][c] += error * 3;
+                    next[i][c] += error * 5;
+                    next[i + 1][c] += error;
+                }
+            }
         }
+
+        std::mem::swap(&mut row, &mut next);
+        next.fill([0; 3]);
+    }
+}
+
+/// The color of an [`Depth::Rgb332`] pixel, with each channel stretched back out to a byte.
+#[cfg(feature = "std")]
+fn rgb332(pixel: u8) -> [u8; 3] {
+    let r = (pixel >> 5) & 0x07;
+    let g = (pixel >> 2) & 0x07;
+    let b = pixel & 0x03;
+
+    let r = (r as u16 * 255 / 7) as u8;
+    let g = (g as u16 * 255 / 7) as u8;
+    let b = (b as u16 * 255 / 3) as u8;
+    [r, g, b]
+}
+
+/// Turns pixels from [`encode`] or [`fit`] back into an image, as long as there's exactly as many
+/// bytes as there should be for its size.
+#[cfg(feature = "std")]
+pub fn decode(bytes: &[u8], width: u32, height: u32, depth: Depth) -> Result<RgbImage, Error> {
+    let expected = len(width, height, depth)?;
+    if bytes.len() != expected {
+        return Err(Error::Size(expected, bytes.len()));
+    }
+
+    let (palette, pixels) = bytes.split_at(depth.palette() as usize);
+    let raw = match depth {
+        Depth::Rgb332 => pixels.iter().flat_map(|pixel| rgb332(*pixel)).collect(),
+        Depth::Rgb888 => pixels.to_vec(),
+        Depth::Indexed => pixels
+            .iter()
+            .flat_map(|index| {
+                let color = *index as usize * 3;
+                [palette[color], palette[color + 1], palette[color + 2]]
+            })
+            .collect(),
+    };
+
+    RgbImage::from_raw(width, height, raw).ok_or(Error::Dimensions(width, height))
+}
+
+/// The passes which a progressive image is sent in, as the first row of each and how many rows
+/// apart its rows are. Every row is in exactly one, and each fills in the gaps of the ones before.
+pub const PASSES: [(u16, u16); 4] = [(0, 8), (4, 8), (2, 4), (1, 2)];
+
+/// One of the [`PASSES`] over an image, for clients with the `PROGRESSIVE` capability.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pass {
+    pub first: u16,
+    pub step: u16,
+
+    /// Every row of the pass, one after another.
+    pub rows: Bytes,
+}
+
+/// Splits pixels from [`encode`] or [`fit`] up into the palette, which is empty unless it's
+/// [`Depth::Indexed`], and the [`PASSES`] which have any rows at all.
+#[cfg(feature = "std")]
+pub fn interlace(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    depth: Depth,
+) -> Result<(Bytes, Vec<Pass>), Error> {
+    let expected = len(width, height, depth)?;
+    if pixels.len() != expected {
+        return Err(Error::Size(expected, pixels.len()));
+    }
+
+    let (palette, pixels) = pixels.split_at(depth.palette() as usize);
+    let row = (width * depth.bytes()) as usize;
+    let passes = PASSES
+        .into_iter()
+        .filter(|(first, _)| (*first as u32) < height)
+        .map(|(first, step)| {
+            let rows = pixels
+                .chunks(row)
+                .skip(first as usize)
+                .step_by(step as usize)
+                .flatten()
+                .copied()
+                .collect();
+            Pass { first, step, rows }
+        })
+        .collect();
+
+    Ok((Bytes::copy_from_slice(palette), passes))
+}
+
+/// Run-length encodes pixels from [`encode`] or [`fit`], for clients with the `RLE` capability.
+///
+/// Each run starts with a control byte `c`. Below `0x80`, the next `c + 1` pixels follow as
+/// they are. Otherwise, a single pixel follows which is repeated `c - 0x7e` times, from 2 up
+/// to 129. A pixel is however many bytes its [`Depth`] takes up.
+#[cfg(feature = "std")]
+pub fn compress(pixels: &[u8], depth: Depth) -> Bytes {
+    const MAX_RUN: usize = 129;
+    const MAX_LITERAL: usize = 128;
+
+    let pixels: Vec<&[u8]> = pixels.chunks(depth.bytes() as usize).collect();
+    let mut bytes = BytesMut::new();
+
+    // How many times the pixel at `start` repeats, up to the longest run a control byte holds.
+    let run = |start: usize| {
+        pixels[start..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|pixel| **pixel == pixels[start])
+            .count()
+    };
+
+    let mut i = 0;
+    while i < pixels.len() {
+        let repeats = run(i);
+        if repeats >= 2 {
+            bytes.put_u8(0x7e + repeats as u8);
+            bytes.put_slice(pixels[i]);
+            i += repeats;
+            continue;
+        }
+
+        // Pixels are sent as they are until a run starts which is long enough to be worth
+        // interrupting them for, since every switch costs another control byte.
+        let mut end = i + 1;
+        while end < pixels.len() && end - i < MAX_LITERAL && run(end) < 3 {
+            end += 1;
+        }
+
+        bytes.put_u8((end - i - 1) as u8);
+        for pixel in &pixels[i..end] {
+            bytes.put_slice(pixel);
+        }
+        i = end;
+    }
+
+    bytes.freeze()
+}
+
+/// Undoes [`compress`], for an image that should take up `size` bytes once it's decompressed.
+#[cfg(feature = "std")]
+pub fn decompress(mut bytes: &[u8], depth: Depth, size: usize) -> Result<BytesMut, Error> {
+    let pixel = depth.bytes() as usize;
+    let mut pixels = BytesMut::with_capacity(size);
+
+    while let Some((&control, rest)) = bytes.split_first() {
+        let (len, repeats) = match control {
+            0x00..0x80 => (pixel * (control as usize + 1), 1),
+            _ => (pixel, control as usize - 0x7e),
+        };
+
+        let Some((run, rest)) = rest.split_at_checked(len) else {
+            return Err(Error::Truncated);
+        };
+        if pixels.len() + len * repeats > size {
+            return Err(Error::Size(size, pixels.len() + len * repeats));
+        }
+
+        for _ in 0..repeats {
+            pixels.put_slice(run);
+        }
+        bytes = rest;
+    }
+
+    if pixels.len() != size {
+        return Err(Error::Size(size, pixels.len()));
     }
 
-    Ok(img)
+    Ok(pixels)
 }
@@ -1,29 +1,103 @@
-use std::{
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use core::{
     net::{Ipv4Addr, SocketAddrV4},
-    ops::IndexMut,
+    ops::{BitAnd, BitOr, Index, IndexMut},
     str::Utf8Error,
 };
 
-use bytes::Bytes;
+#[cfg(feature = "std")]
 use tokio::{
-    io::{BufReader, BufWriter},
+    io::{AsyncRead, AsyncWrite, BufReader, BufWriter},
     net::{
         TcpStream,
         tcp::{OwnedReadHalf, OwnedWriteHalf},
     },
 };
 
+#[cfg(feature = "std")]
+use crate::{deserializers::Deserialize, serializers::Serialize};
+
+#[cfg(feature = "std")]
 pub type Writer = BufWriter<OwnedWriteHalf>;
+#[cfg(feature = "std")]
 pub type Reader = BufReader<OwnedReadHalf>;
 
+mod codec;
+#[cfg(feature = "std")]
 pub mod deserializers;
+#[cfg(feature = "std")]
+pub mod framed;
 pub mod image;
 pub mod lobby;
+pub mod serial;
+#[cfg(feature = "std")]
 pub mod serializers;
+pub mod sync;
+
+pub use codec::{Color, Depth, Direction, Dither, PROTOCOL_VERSION, Packet, ROOM_CODE_LEN, Reason};
 
 pub const PORT: u16 = 3000;
 pub const LOCALHOST: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), PORT);
 
+/// The code of the room which clients are put in when they first connect.
+pub const DEFAULT_ROOM: &str = "MAIN";
+
+/// Upper bounds on variable length fields, so that a bogus length read off
+/// the wire can't make the other side allocate gigabytes of memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// Maximum length of an image, in bytes.
+    pub image: usize,
+
+    /// Maximum amount of players in a lobby, round or the final standings.
+    pub players: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            // A full size image in `Rgb888` is the biggest that the server ever sends, and
+            // run-length encoding it can add a control byte for every 128 pixels at worst.
+            image: image::SIZE as usize * 3 + image::SIZE.div_ceil(128) as usize,
+            players: 64,
+        }
+    }
+}
+
+/// Optional features which either side of a connection supports, as a bitfield.
+/// The server won't send clients packets belonging to a feature which they lack.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Capabilities(pub u32);
+
+// Each of the flags is generated from `protocol.json`, along with `ALL`.
+impl Capabilities {
+    pub const NONE: Self = Self(0);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Capabilities {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct RoundResult {
     pub number: usize,
@@ -31,13 +105,33 @@ pub struct RoundResult {
     pub players: Vec<Player>,
 }
 
-use std::ops::Index;
+/// A player's final standing at the end of a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Standing {
+    pub id: usize,
+    pub points: u32,
+
+    /// How far off all of the player's guesses were combined, in kilometers. A round without a
+    /// guess counts as far off as a guess can be, which is halfway around the world.
+    pub distance: f32,
+}
 
-use crate::{
-    deserializers::Deserialize,
-    lobby::{Action, Clients},
-    serializers::Serialize,
-};
+/// The closest guess anyone made during a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BestGuess {
+    pub id: usize,
+    pub round: usize,
+
+    /// How far off the guess was, in kilometers.
+    pub distance: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameResult {
+    /// Every player's standing, sorted from first to last place.
+    pub standings: Vec<Standing>,
+    pub best: Option<BestGuess>,
+}
 
 impl Index<usize> for RoundResult {
     type Output = Player;
@@ -59,15 +153,24 @@ impl IndexMut<usize> for RoundResult {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-#[repr(u8)]
-pub enum Color {
-    Red = 0,
-    Green,
-    Blue,
-    Cyan,
-    Magenta,
-    Yellow,
+impl Direction {
+    /// The slice to the left of this one, if there is one.
+    pub fn left(self) -> Option<Self> {
+        match self {
+            Direction::Left => None,
+            Direction::Center => Some(Direction::Left),
+            Direction::Right => Some(Direction::Center),
+        }
+    }
+
+    /// The slice to the right of this one, if there is one.
+    pub fn right(self) -> Option<Self> {
+        match self {
+            Direction::Left => Some(Direction::Center),
+            Direction::Center => Some(Direction::Right),
+            Direction::Right => None,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -76,6 +179,31 @@ pub struct ClientOptions {
     pub user: String,
 }
 
+/// What images a client wants, which the server fits every image it sends the client to.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Display {
+    pub width: u16,
+    pub height: u16,
+    pub depth: Depth,
+    pub dither: Dither,
+
+    /// The most bytes that a single image can take up, or zero for no limit.
+    pub max_payload: u32,
+}
+
+impl Default for Display {
+    /// A full size image in [`Depth::Rgb332`], which is what a calculator shows.
+    fn default() -> Self {
+        Self {
+            width: image::WIDTH as u16,
+            height: image::HEIGHT as u16,
+            depth: Depth::Rgb332,
+            dither: Dither::None,
+            max_payload: 0,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub struct Coordinate {
     /// North-South, like the "y".
@@ -93,70 +221,18 @@ pub struct Player {
     pub id: usize,
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum Packet {
-    Init {
-        options: ClientOptions,
-    },
-    Confirmed {
-        id: usize,
-        options: ClientOptions,
-        lobby: Clients,
-    },
-    LobbyEvent {
-        action: Action,
-        user: usize,
-        lobby: Clients,
-    },
-    WaitingStatus {
-        ready: bool,
-    },
-    RoundLoading {
-        lobby: Clients,
-    },
-    Round {
-        number: usize,
-        image: Bytes,
-    },
-    Guess {
-        coordinates: Coordinate,
-    },
-    Guessed {
-        player: usize,
-    },
-    Result {
-        results: RoundResult,
-    },
-    RequestGameEnd,
-    SoftQuit,
-}
-
-impl Packet {
-    pub fn tag(&self) -> u8 {
-        match self {
-            Packet::Init { .. } => 1,
-            Packet::Confirmed { .. } => 2,
-            Packet::LobbyEvent { .. } => 3,
-            Packet::WaitingStatus { .. } => 4,
-            Packet::RoundLoading { .. } => 5,
-            Packet::Round { .. } => 6,
-            Packet::Guess { .. } => 7,
-            Packet::Guessed { .. } => 8,
-            Packet::Result { .. } => 9,
-            Packet::RequestGameEnd => 10,
-            Packet::SoftQuit => 11,
-        }
-    }
-}
-
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("socket closed")]
     Close,
 
+    #[cfg(feature = "std")]
     #[error("io failure")]
     Io(#[from] std::io::Error),
 
+    #[error("packet is incomplete")]
+    Incomplete,
+
     #[error("utf8 error")]
     Utf8(#[from] Utf8Error),
 
@@ -165,12 +241,48 @@ pub enum Error {
 
     #[error("unknown packet: {0:?}")]
     Unknown(u8),
+
+    #[error("unsupported protocol version: {0}")]
+    Version(u16),
+
+    #[error("invalid {0}: {1}")]
+    Invalid(&'static str, u8),
+
+    #[error("{0} is too long: {1} > {2}")]
+    TooLong(&'static str, usize, usize),
+
+    #[error("checksum mismatch: expected {0:#06x}, got {1:#06x}")]
+    Checksum(u16, u16),
+
+    #[error("packet doesn't fill its {0} byte frame exactly")]
+    Length(usize),
+}
+
+impl Error {
+    /// What to tell the other side about this error, or [`None`] if the connection is gone.
+    pub fn reason(&self) -> Option<Reason> {
+        match self {
+            Error::Close | Error::Incomplete => None,
+            #[cfg(feature = "std")]
+            Error::Io(_) => None,
+            Error::Utf8(_)
+            | Error::Invalid(..)
+            | Error::TooLong(..)
+            | Error::Checksum(..)
+            | Error::Length(_) => Some(Reason::Malformed),
+            Error::Illegal(_) => Some(Reason::Illegal),
+            Error::Unknown(_) => Some(Reason::Unknown),
+            Error::Version(_) => Some(Reason::Version),
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 pub trait BufferedSplitExt {
     fn buffered_split(self) -> (Reader, Writer);
 }
 
+#[cfg(feature = "std")]
 impl BufferedSplitExt for TcpStream {
     fn buffered_split(self) -> (Reader, Writer) {
         let (reader, writer) = self.into_split();
@@ -178,23 +290,34 @@ impl BufferedSplitExt for TcpStream {
     }
 }
 
+#[cfg(feature = "std")]
 pub trait PacketReadExt {
-    fn read_packet(&mut self) -> impl Future<Output = Result<Packet, Error>> + Send;
+    fn read_packet(&mut self) -> impl Future<Output = Result<Packet, Error>> + Send {
+        self.read_packet_with(Limits::default())
+    }
+
+    fn read_packet_with(
+        &mut self,
+        limits: Limits,
+    ) -> impl Future<Output = Result<Packet, Error>> + Send;
 }
 
+#[cfg(feature = "std")]
 pub trait PacketWriteExt {
     fn write_packet(&mut self, packet: Packet) -> impl Future<Output = Result<(), Error>> + Send;
 }
 
-impl PacketWriteExt for Writer {
+#[cfg(feature = "std")]
+impl<W: AsyncWrite + Unpin + Send> PacketWriteExt for W {
     async fn write_packet(&mut self, packet: Packet) -> Result<(), Error> {
         packet.serialize(self).await?;
         Ok(())
     }
 }
 
-impl PacketReadExt for Reader {
-    async fn read_packet(&mut self) -> Result<Packet, Error> {
-        Packet::deserialize(self).await
+#[cfg(feature = "std")]
+impl<R: AsyncRead + Unpin + Send> PacketReadExt for R {
+    async fn read_packet_with(&mut self, limits: Limits) -> Result<Packet, Error> {
+        Packet::deserialize_with(self, limits).await
     }
 }
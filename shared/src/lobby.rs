@@ -1,7 +1,10 @@
-use std::ops::Index;
+use alloc::vec::Vec;
+use core::ops::Index;
 
 use crate::ClientOptions;
 
+pub use crate::codec::Action;
+
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct Clients {
     inner: Vec<Client>,
@@ -20,7 +23,7 @@ impl Index<usize> for Clients {
 
 impl<'a> IntoIterator for &'a Clients {
     type Item = &'a Client;
-    type IntoIter = std::slice::Iter<'a, Client>;
+    type IntoIter = core::slice::Iter<'a, Client>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.inner.iter()
@@ -29,7 +32,7 @@ impl<'a> IntoIterator for &'a Clients {
 
 impl<'a> IntoIterator for &'a mut Clients {
     type Item = &'a mut Client;
-    type IntoIter = std::slice::IterMut<'a, Client>;
+    type IntoIter = core::slice::IterMut<'a, Client>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.inner.iter_mut()
@@ -45,6 +48,10 @@ impl Clients {
         self.inner.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
     pub fn into_inner(self) -> Vec<Client> {
         self.inner
     }
@@ -62,12 +69,3 @@ pub struct Client {
     pub ready: bool,
     pub options: ClientOptions,
 }
-
-#[derive(Debug, PartialEq, Clone, Copy)]
-#[repr(u8)]
-pub enum Action {
-    Join = 0,
-    Return,
-    Leave,
-    Ready,
-}
@@ -0,0 +1,115 @@
+//! Optional framing for the serial link between a transponder and a calculator.
+//!
+//! The raw protocol has no packet boundaries, so a single corrupted byte on the cable
+//! garbles every packet after it. Instead, each packet can be wrapped in a frame:
+//!
+//! | Field    | Type        | Size (bytes) | Description                               |
+//! | -------- | ----------- | ------------ | ----------------------------------------- |
+//! | `sync`   | `byte[2]`   | 2            | Always [`SYNC`].                          |
+//! | `len`    | `uint32`    | 4            | Length of `packet`.                       |
+//! | `packet` | `byte[len]` | variable     | The packet, exactly as it'd be sent raw.  |
+//! | `crc`    | `uint16`    | 2            | CRC-16/CCITT-FALSE of `len` and `packet`. |
+//!
+//! Anything which isn't a valid frame is skipped until the next [`SYNC`], so the
+//! link recovers on its own from line noise or a calculator being unplugged mid-packet.
+
+use alloc::vec::Vec;
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use crate::{
+    Error, Limits, Packet,
+    sync::{Decode, Encode},
+};
+
+/// Marks the start of every frame. Neither byte is a valid tag, so raw packets never start with it.
+pub const SYNC: [u8; 2] = [0xA5, 0x5A];
+
+/// How many bytes a frame adds on top of its packet.
+pub const OVERHEAD: usize = SYNC.len() + 4 + 2;
+
+/// CRC-16/CCITT-FALSE, which is simple enough to compute a bit at a time on a calculator.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFF;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+/// The longest packet which could fit within the limits, so that a corrupted length
+/// doesn't leave the decoder waiting forever for bytes which will never come.
+fn max(limits: Limits) -> usize {
+    // None of the structs in a list are anywhere near 32 bytes, nor the rest of a packet 64.
+    limits.image + limits.players * 32 + 64
+}
+
+/// Writes a packet wrapped in a frame.
+pub fn encode<B: BufMut>(packet: &Packet, buf: &mut B) {
+    let mut body = Vec::new();
+    body.put_u32(0);
+    packet.encode(&mut body);
+
+    let len = (body.len() - 4) as u32;
+    body[..4].copy_from_slice(&len.to_be_bytes());
+
+    buf.put_slice(&SYNC);
+    buf.put_slice(&body);
+    buf.put_u16(crc16(&body));
+}
+
+/// Reads the next frame out of `src`, skipping past anything that came before it.
+///
+/// Returns [`None`] if the frame isn't all there yet. An error means that a corrupted
+/// frame was dropped, and that decoding can carry on right where it left off.
+pub fn decode(src: &mut BytesMut, limits: Limits) -> Result<Option<Packet>, Error> {
+    let Some(start) = src.windows(SYNC.len()).position(|x| x == SYNC) else {
+        // The first half of the sync bytes might be at the very end, with the rest on its way.
+        let keep = usize::from(src.last() == Some(&SYNC[0]));
+        src.advance(src.len() - keep);
+        return Ok(None);
+    };
+    src.advance(start);
+
+    let Some(header) = src.get(SYNC.len()..SYNC.len() + 4) else {
+        return Ok(None);
+    };
+    let len = u32::from_be_bytes(header.try_into().unwrap()) as usize;
+    let max = max(limits);
+    if len > max {
+        src.advance(1);
+        return Err(Error::TooLong("frame", len, max));
+    }
+
+    if src.len() < OVERHEAD + len {
+        return Ok(None);
+    }
+
+    let body = &src[SYNC.len()..SYNC.len() + 4 + len];
+    let expected = u16::from_be_bytes([src[OVERHEAD + len - 2], src[OVERHEAD + len - 1]]);
+    let actual = crc16(body);
+    if actual != expected {
+        // This might've been a stray pair of sync bytes, so there could be a frame inside of it.
+        src.advance(1);
+        return Err(Error::Checksum(expected, actual));
+    }
+
+    let mut packet = &body[4..];
+    let result = Packet::decode_with(&mut packet, limits);
+    let leftover = packet.len();
+    src.advance(OVERHEAD + len);
+
+    match result {
+        Ok(packet) if leftover == 0 => Ok(Some(packet)),
+        Ok(_) | Err(Error::Incomplete) => Err(Error::Length(len)),
+        Err(error) => Err(error),
+    }
+}
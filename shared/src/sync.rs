@@ -0,0 +1,124 @@
+//! A synchronous codec for packets, which reads from any [`Buf`] and writes to any [`BufMut`].
+//!
+//! Unlike [`serializers`](crate::serializers) and [`deserializers`](crate::deserializers),
+//! this doesn't need tokio or even the standard library, just `alloc`, so that firmware like
+//! an ESP32 transponder can use the exact same implementation of the protocol.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
+use bytes::{Buf, BufMut, Bytes, TryGetError};
+
+use crate::{Error, Limits, PROTOCOL_VERSION};
+
+impl From<TryGetError> for Error {
+    fn from(_: TryGetError) -> Self {
+        Error::Incomplete
+    }
+}
+
+pub(crate) trait ToFixed<const LEN: usize> {
+    fn fixed(&self) -> [u8; LEN];
+}
+
+impl<const LEN: usize> ToFixed<LEN> for String {
+    fn fixed(&self) -> [u8; LEN] {
+        let mut writer = [0u8; LEN];
+        let slice = self.as_bytes();
+        let len = slice.len().min(LEN);
+        writer[..len].copy_from_slice(&slice[..len]);
+
+        writer
+    }
+}
+
+pub trait Encode {
+    /// Writes a value to the end of a buffer, which has to have enough room for it.
+    fn encode<B: BufMut>(&self, buf: &mut B);
+}
+
+pub trait Decode: Sized {
+    /// Reads a value, failing with [`Error::Incomplete`] if the buffer ends before it does,
+    /// or if any variable length field inside of it goes over the limits.
+    fn decode_with<B: Buf>(buf: &mut B, limits: Limits) -> Result<Self, Error>;
+
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self, Error> {
+        Self::decode_with(buf, Limits::default())
+    }
+}
+
+/// Makes sure that a buffer has at least `len` more bytes in it.
+fn ensure<B: Buf>(buf: &B, len: usize) -> Result<(), Error> {
+    if buf.remaining() < len {
+        return Err(Error::Incomplete);
+    }
+
+    Ok(())
+}
+
+/// Reads a length, making sure that it's not over the maximum.
+pub(crate) fn len<B: Buf>(buf: &mut B, field: &'static str, max: usize) -> Result<usize, Error> {
+    let len = buf.try_get_u32()? as usize;
+    if len > max {
+        return Err(Error::TooLong(field, len, max));
+    }
+
+    Ok(len)
+}
+
+/// Reads a fixed length, null padded string.
+pub(crate) fn fixed<B: Buf, const LEN: usize>(buf: &mut B) -> Result<String, Error> {
+    let mut fixed = [0; LEN];
+    buf.try_copy_to_slice(&mut fixed)?;
+    Ok(core::str::from_utf8(&fixed)?
+        .trim_end_matches('\0')
+        .to_owned())
+}
+
+/// Checks the protocol version at the start of a handshake.
+pub(crate) fn version<B: Buf>(buf: &mut B) -> Result<(), Error> {
+    let version = buf.try_get_u16()?;
+    if version != PROTOCOL_VERSION {
+        return Err(Error::Version(version));
+    }
+
+    Ok(())
+}
+
+/// Reads a length prefixed blob of bytes.
+pub(crate) fn bytes<B: Buf>(buf: &mut B, field: &'static str, max: usize) -> Result<Bytes, Error> {
+    let len = len(buf, field, max)?;
+    ensure(buf, len)?;
+
+    Ok(buf.copy_to_bytes(len))
+}
+
+/// Reads a length prefixed list of values.
+pub(crate) fn list<B: Buf, T: Decode>(
+    buf: &mut B,
+    field: &'static str,
+    max: usize,
+    limits: Limits,
+) -> Result<Vec<T>, Error> {
+    let len = len(buf, field, max)?;
+    let mut list = Vec::with_capacity(len);
+    for _ in 0..len {
+        list.push(T::decode_with(buf, limits)?);
+    }
+
+    Ok(list)
+}
+
+/// Reads a flag followed by a value, which is just `size` bytes of padding if the flag isn't set.
+pub(crate) fn optional<B: Buf, T: Decode>(
+    buf: &mut B,
+    size: usize,
+    limits: Limits,
+) -> Result<Option<T>, Error> {
+    if buf.try_get_u8()? != 0 {
+        return Ok(Some(T::decode_with(buf, limits)?));
+    }
+
+    ensure(buf, size)?;
+    buf.advance(size);
+    Ok(None)
+}
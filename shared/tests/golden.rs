@@ -0,0 +1,613 @@
+//! Checks every packet against the byte layout documented in `PROTOCOL.md`.
+//!
+//! Setting `GEOTERM_FIXTURES` to a directory also writes out each packet as `{name}.bin`,
+//! and wrapped in a serial frame as `{name}.frame`, which the TI84 client's C (de)serializers
+//! are checked against in `clients/ti84/tests`.
+
+use std::{collections::BTreeSet, path::PathBuf};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::executor::block_on;
+use shared::{
+    BestGuess, Capabilities, ClientOptions, Color, Coordinate, Depth, Direction, Display, Dither,
+    Error, GameResult, PROTOCOL_VERSION, Packet, Player, Reason, RoundResult, Standing,
+    deserializers::Deserialize,
+    framed::{FrameCodec, PacketCodec},
+    lobby::{self, Action, Clients},
+    serial::{self, SYNC},
+    serializers::Serialize,
+    sync::{Decode, Encode},
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Builds the expected bytes of a packet field by field.
+#[derive(Default)]
+struct Wire(Vec<u8>);
+
+impl Wire {
+    fn tag(tag: u8) -> Self {
+        Self(vec![tag])
+    }
+
+    fn u8(mut self, value: u8) -> Self {
+        self.0.push(value);
+        self
+    }
+
+    fn u16(mut self, value: u16) -> Self {
+        self.0.extend(value.to_be_bytes());
+        self
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+        self.0.extend(value.to_be_bytes());
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Self {
+        self.0.extend(value.to_be_bytes());
+        self
+    }
+
+    fn f32(mut self, value: f32) -> Self {
+        self.0.extend(value.to_be_bytes());
+        self
+    }
+
+    fn bytes(mut self, value: &[u8]) -> Self {
+        self.0.extend(value);
+        self
+    }
+
+    /// A null padded string.
+    fn fixed(self, value: &str, len: usize) -> Self {
+        let mut buf = vec![0; len];
+        buf[..value.len()].copy_from_slice(value.as_bytes());
+        self.bytes(&buf)
+    }
+
+    fn coordinate(self, coordinate: Coordinate) -> Self {
+        self.f32(coordinate.latitude).f32(coordinate.longitude)
+    }
+
+    fn options(self, options: &ClientOptions) -> Self {
+        self.u8(options.color as u8).fixed(&options.user, 16)
+    }
+
+    fn clients(self, clients: &Clients) -> Self {
+        clients
+            .into_iter()
+            .fold(self.u32(clients.len() as u32), |wire, client| {
+                wire.u32(client.id as u32)
+                    .u8(client.ready as u8)
+                    .options(&client.options)
+            })
+    }
+
+    fn player(self, player: &Player) -> Self {
+        let wire = self
+            .u32(player.id as u32)
+            .u32(player.points)
+            .u32(player.delta)
+            .u8(player.guess.is_some() as u8);
+
+        match player.guess {
+            Some(guess) => wire.coordinate(guess),
+            None => wire.bytes(&[0; 8]),
+        }
+    }
+}
+
+fn options() -> ClientOptions {
+    ClientOptions {
+        color: Color::Cyan,
+        user: String::from("bobby"),
+    }
+}
+
+fn clients() -> Clients {
+    Clients::from(vec![
+        lobby::Client {
+            id: 3,
+            ready: true,
+            options: options(),
+        },
+        lobby::Client {
+            id: 7,
+            ready: false,
+            options: ClientOptions {
+                color: Color::Yellow,
+                user: String::from("sixteen chars!!!"),
+            },
+        },
+    ])
+}
+
+fn round() -> RoundResult {
+    RoundResult {
+        number: 2,
+        answer: Coordinate {
+            latitude: 48.5,
+            longitude: 19.25,
+        },
+        players: vec![
+            Player {
+                guess: Some(Coordinate {
+                    latitude: -33.75,
+                    longitude: 151.125,
+                }),
+                points: 1500,
+                delta: 250,
+                id: 3,
+            },
+            Player {
+                guess: None,
+                points: 900,
+                delta: 0,
+                id: 7,
+            },
+        ],
+    }
+}
+
+fn game() -> GameResult {
+    GameResult {
+        standings: vec![
+            Standing {
+                id: 3,
+                points: 1500,
+                distance: 1234.5,
+            },
+            Standing {
+                id: 7,
+                points: 900,
+                distance: 4321.25,
+            },
+        ],
+        best: Some(BestGuess {
+            id: 3,
+            round: 1,
+            distance: 12.75,
+        }),
+    }
+}
+
+/// A game with more players than the TI84 client has room to show.
+fn crowded() -> GameResult {
+    GameResult {
+        standings: (0..20)
+            .map(|id| Standing {
+                id,
+                points: id as u32 * 100,
+                distance: id as f32,
+            })
+            .collect(),
+        best: Some(BestGuess {
+            id: 19,
+            round: 4,
+            distance: 0.5,
+        }),
+    }
+}
+
+/// An image which is easy to spot if it's off by a byte.
+fn image() -> Bytes {
+    Bytes::from_iter((0..=255).chain(0..=255))
+}
+
+/// A run-length encoded image, which is two sevens, `1, 2, 3` as they are,
+/// and then 129 nines.
+fn rle() -> Bytes {
+    Bytes::from_static(&[0x80, 7, 0x02, 1, 2, 3, 0xff, 9])
+}
+
+/// Every packet, along with a name for its fixture and its expected bytes.
+fn samples() -> Vec<(&'static str, Packet, Wire)> {
+    let round = round();
+    let game = game();
+    let crowded = crowded();
+
+    vec![
+        (
+            "init",
+            Packet::Init {
+                capabilities: Capabilities::LOOK | Capabilities::STANDINGS,
+                options: options(),
+                display: Display {
+                    width: 160,
+                    height: 120,
+                    depth: Depth::Indexed,
+                    dither: Dither::Ordered,
+                    max_payload: 0x12345,
+                },
+            },
+            Wire::tag(1)
+                .u16(PROTOCOL_VERSION)
+                .u32(0b11)
+                .options(&options())
+                .u16(160)
+                .u16(120)
+                .u8(2)
+                .u8(2)
+                .u32(0x12345),
+        ),
+        (
+            "confirmed",
+            Packet::Confirmed {
+                capabilities: Capabilities::ALL,
+                id: 3,
+                token: 0x0123_4567_89ab_cdef,
+                options: options(),
+                lobby: clients(),
+            },
+            Wire::tag(2)
+                .u16(PROTOCOL_VERSION)
+                .u32(0b111_1111)
+                .u32(3)
+                .u64(0x0123_4567_89ab_cdef)
+                .options(&options())
+                .clients(&clients()),
+        ),
+        (
+            "lobby_event",
+            Packet::LobbyEvent {
+                action: Action::Leave,
+                user: 7,
+                lobby: clients(),
+            },
+            Wire::tag(3).u8(2).u32(7).clients(&clients()),
+        ),
+        (
+            "waiting_status",
+            Packet::WaitingStatus { ready: true },
+            Wire::tag(4).u8(1),
+        ),
+        (
+            "round_loading",
+            Packet::RoundLoading { lobby: clients() },
+            Wire::tag(5).clients(&clients()),
+        ),
+        (
+            "round",
+            Packet::Round {
+                number: 2,
+                deadline: 30,
+                width: 32,
+                height: 16,
+                image: image(),
+            },
+            Wire::tag(6)
+                .u32(2)
+                .u32(30)
+                .u16(32)
+                .u16(16)
+                .u32(512)
+                .bytes(&image()),
+        ),
+        (
+            "round_rle",
+            Packet::Round {
+                number: 2,
+                deadline: 30,
+                width: 67,
+                height: 2,
+                image: rle(),
+            },
+            Wire::tag(6)
+                .u32(2)
+                .u32(30)
+                .u16(67)
+                .u16(2)
+                .u32(8)
+                .bytes(&rle()),
+        ),
+        (
+            "guess",
+            Packet::Guess {
+                coordinates: round.answer,
+            },
+            Wire::tag(7).f32(48.5).f32(19.25),
+        ),
+        (
+            "guessed",
+            Packet::Guessed { player: 7 },
+            Wire::tag(8).u32(7),
+        ),
+        (
+            "result",
+            Packet::Result {
+                results: round.clone(),
+            },
+            Wire::tag(9)
+                .u32(2)
+                .coordinate(round.answer)
+                .u32(2)
+                .player(&round.players[0])
+                .player(&round.players[1]),
+        ),
+        ("request_game_end", Packet::RequestGameEnd, Wire::tag(10)),
+        ("soft_quit", Packet::SoftQuit, Wire::tag(11)),
+        (
+            "look",
+            Packet::Look {
+                direction: Direction::Right,
+            },
+            Wire::tag(12).u8(2),
+        ),
+        (
+            "view",
+            Packet::View {
+                direction: Direction::Left,
+                width: 16,
+                height: 32,
+                image: image(),
+            },
+            Wire::tag(13).u8(0).u16(16).u16(32).u32(512).bytes(&image()),
+        ),
+        (
+            "game_over",
+            Packet::GameOver {
+                results: game.clone(),
+            },
+            Wire::tag(14)
+                .u32(2)
+                .u32(3)
+                .u32(1500)
+                .f32(1234.5)
+                .u32(7)
+                .u32(900)
+                .f32(4321.25)
+                .u8(1)
+                .u32(3)
+                .u32(1)
+                .f32(12.75),
+        ),
+        (
+            "game_over_no_best",
+            Packet::GameOver {
+                results: GameResult {
+                    standings: Vec::new(),
+                    best: None,
+                },
+            },
+            Wire::tag(14).u32(0).u8(0).bytes(&[0; 12]),
+        ),
+        (
+            "game_over_crowded",
+            Packet::GameOver {
+                results: crowded.clone(),
+            },
+            crowded
+                .standings
+                .iter()
+                .fold(Wire::tag(14).u32(20), |wire, standing| {
+                    wire.u32(standing.id as u32)
+                        .u32(standing.points)
+                        .f32(standing.distance)
+                })
+                .u8(1)
+                .u32(19)
+                .u32(4)
+                .f32(0.5),
+        ),
+        ("create_room", Packet::CreateRoom, Wire::tag(15)),
+        (
+            "join_room",
+            Packet::JoinRoom {
+                code: String::from("QWER"),
+            },
+            Wire::tag(16).fixed("QWER", 4),
+        ),
+        (
+            "room",
+            Packet::Room {
+                code: String::from("QWER"),
+                lobby: clients(),
+            },
+            Wire::tag(17).fixed("QWER", 4).clients(&clients()),
+        ),
+        (
+            "room_unavailable",
+            Packet::RoomUnavailable {
+                code: String::from("ZZZZ"),
+            },
+            Wire::tag(18).fixed("ZZZZ", 4),
+        ),
+        (
+            "resume",
+            Packet::Resume {
+                token: 0x0123_4567_89ab_cdef,
+            },
+            Wire::tag(19).u64(0x0123_4567_89ab_cdef),
+        ),
+        ("resume_failed", Packet::ResumeFailed, Wire::tag(20)),
+        (
+            "error",
+            Packet::Error {
+                reason: Reason::Version,
+                version: PROTOCOL_VERSION,
+            },
+            Wire::tag(21).u8(0).u16(PROTOCOL_VERSION),
+        ),
+        ("disconnected", Packet::Disconnected, Wire::tag(22)),
+        (
+            "rows",
+            Packet::Rows {
+                direction: Direction::Left,
+                first: 4,
+                step: 8,
+                image: image().slice(..64),
+            },
+            Wire::tag(23)
+                .u8(0)
+                .u16(4)
+                .u16(8)
+                .u32(64)
+                .bytes(&image()[..64]),
+        ),
+    ]
+}
+
+/// Wraps the bytes of a packet in a serial frame.
+fn frame(wire: &[u8]) -> Vec<u8> {
+    let mut body = (wire.len() as u32).to_be_bytes().to_vec();
+    body.extend_from_slice(wire);
+
+    let mut frame = SYNC.to_vec();
+    frame.extend_from_slice(&body);
+    frame.extend_from_slice(&serial::crc16(&body).to_be_bytes());
+    frame
+}
+
+#[test]
+fn serialize() {
+    for (name, packet, wire) in samples() {
+        let mut buf = Vec::new();
+        block_on(packet.serialize(&mut buf)).unwrap();
+        assert_eq!(buf, wire.0, "{name} doesn't match its documented layout");
+    }
+}
+
+#[test]
+fn deserialize() {
+    for (name, packet, wire) in samples() {
+        let mut reader = wire.0.as_slice();
+        let decoded = block_on(Packet::deserialize(&mut reader)).unwrap();
+        assert_eq!(decoded, packet, "{name} was read incorrectly");
+        assert!(
+            reader.is_empty(),
+            "{name} left {} bytes unread",
+            reader.len()
+        );
+    }
+}
+
+#[test]
+fn sync() {
+    for (name, packet, wire) in samples() {
+        let mut buf = Vec::new();
+        packet.encode(&mut buf);
+        assert_eq!(buf, wire.0, "{name} doesn't match its documented layout");
+
+        let mut reader = wire.0.as_slice();
+        let decoded = Packet::decode(&mut reader).unwrap();
+        assert_eq!(decoded, packet, "{name} was read incorrectly");
+        assert!(
+            reader.is_empty(),
+            "{name} left {} bytes unread",
+            reader.len()
+        );
+
+        // Cutting a packet short anywhere should only ever mean it's incomplete.
+        for len in 0..wire.0.len() {
+            let mut reader = &wire.0[..len];
+            assert!(
+                matches!(Packet::decode(&mut reader), Err(Error::Incomplete)),
+                "{name} cut off at {len} bytes wasn't incomplete"
+            );
+        }
+    }
+}
+
+#[test]
+fn framed() {
+    let samples = samples();
+    let packets: Vec<Packet> = samples
+        .iter()
+        .map(|(_, packet, _)| packet.clone())
+        .collect();
+    let wire: Vec<u8> = samples
+        .iter()
+        .flat_map(|(_, _, wire)| wire.0.clone())
+        .collect();
+
+    let mut codec = PacketCodec::default();
+    let mut encoded = BytesMut::new();
+    for packet in &packets {
+        codec.encode(packet.clone(), &mut encoded).unwrap();
+    }
+    assert_eq!(encoded, wire);
+
+    // Every packet gets split up, as if it trickled in over a slow serial connection.
+    let mut buf = BytesMut::new();
+    let mut decoded = Vec::new();
+    for byte in wire {
+        buf.put_u8(byte);
+        while let Some(packet) = codec.decode(&mut buf).unwrap() {
+            decoded.push(packet);
+        }
+    }
+    assert_eq!(decoded, packets);
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn crc() {
+    // The standard check value for CRC-16/CCITT-FALSE.
+    assert_eq!(serial::crc16(b"123456789"), 0x29B1);
+}
+
+#[test]
+fn frames() {
+    let mut codec = FrameCodec::default();
+    let mut stream = BytesMut::new();
+    let mut packets = Vec::new();
+    for (name, packet, wire) in samples() {
+        let mut encoded = BytesMut::new();
+        codec.encode(packet.clone(), &mut encoded).unwrap();
+        assert_eq!(encoded, frame(&wire.0), "{name} wasn't framed correctly");
+
+        // Line noise, followed by a copy of the frame with a bit flipped in the last byte
+        // of its packet, both of which should be skipped over.
+        let mut corrupted = encoded.to_vec();
+        let last = corrupted.len() - 3;
+        corrupted[last] ^= 0x10;
+        stream.put_slice(&[0x00, SYNC[0], 0x13, SYNC[0]]);
+        stream.put_slice(&corrupted);
+        stream.put_slice(&encoded);
+        packets.push(packet);
+    }
+
+    let mut buf = BytesMut::new();
+    let mut decoded = Vec::new();
+    let mut dropped = 0;
+    for byte in stream {
+        buf.put_u8(byte);
+        while let Some(frame) = codec.decode(&mut buf).unwrap() {
+            match frame {
+                Ok(packet) => decoded.push(packet),
+                Err(Error::Checksum(..)) => dropped += 1,
+                Err(error) => panic!("unexpected error: {error}"),
+            }
+        }
+    }
+    assert_eq!(decoded, packets);
+    assert_eq!(dropped, packets.len());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn tags() {
+    let samples = samples();
+    for (name, packet, wire) in &samples {
+        assert_eq!(packet.tag(), wire.0[0], "{name} has the wrong tag");
+    }
+
+    // Adding a packet without a sample here should fail this.
+    let covered: BTreeSet<u8> = samples.iter().map(|(_, packet, _)| packet.tag()).collect();
+    let expected: BTreeSet<u8> = (1..=23).collect();
+    assert_eq!(covered, expected);
+}
+
+#[test]
+fn fixtures() {
+    let Some(directory) = std::env::var_os("GEOTERM_FIXTURES").map(PathBuf::from) else {
+        return;
+    };
+
+    std::fs::create_dir_all(&directory).unwrap();
+    for (name, _, wire) in samples() {
+        std::fs::write(directory.join(format!("{name}.frame")), frame(&wire.0)).unwrap();
+        std::fs::write(directory.join(format!("{name}.bin")), wire.0).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+//! Checks the quantizers and run-length encoding in `shared::image` on a real street view image,
+//! which is the kind of thing that they're meant for.
+
+use std::sync::LazyLock;
+
+use image::{GenericImageView, ImageReader, Rgb, RgbImage, imageops};
+use shared::{
+    Depth, Display, Dither, Limits,
+    image::{
+        Error, HEIGHT, SIZE, WIDTH, compress, decode, decompress, encode, fit, interlace, len,
+    },
+};
+
+/// The three slices of `misc/demo.jpg`, cut up the same way the server does it.
+static SLICES: LazyLock<Vec<RgbImage>> = LazyLock::new(|| {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../misc/demo.jpg");
+    let panorama = ImageReader::open(path)
+        .unwrap()
+        .decode()
+        .unwrap()
+        .into_rgb8();
+
+    let (width, height) = panorama.dimensions();
+    let slice = width / 3;
+    (0..3)
+        .map(|i| {
+            let view = panorama.view(slice * i, 0, slice, height).to_image();
+            imageops::resize(&view, WIDTH, HEIGHT, imageops::FilterType::Lanczos3)
+        })
+        .collect()
+});
+
+fn display(depth: Depth) -> Display {
+    Display {
+        depth,
+        ..Display::default()
+    }
+}
+
+/// Compresses every slice, checking that it decompresses back to the same thing, and returns how
+/// big the compressed slices are compared to the raw ones.
+fn ratio(display: &Display) -> f64 {
+    let (mut raw, mut compressed) = (0, 0);
+    for slice in SLICES.iter() {
+        let pixels = fit(slice, display).unwrap();
+        let rle = compress(&pixels, display.depth);
+        let size = display.size() as usize;
+        assert_eq!(decompress(&rle, display.depth, size).unwrap(), pixels);
+
+        raw += pixels.len();
+        compressed += rle.len();
+    }
+
+    let ratio = compressed as f64 / raw as f64;
+    eprintln!("{display:?}: {compressed} of {raw} bytes, {ratio:.2}");
+    ratio
+}
+
+#[test]
+fn compresses_rgb332() {
+    // What a calculator gets, and where it matters the most.
+    assert!(ratio(&display(Depth::Rgb332)) < 0.5);
+}
+
+#[test]
+fn compresses_rgb888() {
+    // Full color has a lot fewer runs, but it still shouldn't get any bigger.
+    assert!(ratio(&display(Depth::Rgb888)) < 1.0);
+}
+
+#[test]
+fn compresses_scaled_down() {
+    let display = Display {
+        width: 160,
+        height: 120,
+        ..display(Depth::Rgb332)
+    };
+    assert!(ratio(&display) < 0.5);
+}
+
+#[test]
+fn worst_case_fits_limits() {
+    // No two neighbouring pixels are the same, so nothing can be run-length encoded.
+    let pixels: Vec<u8> = (0..SIZE * 3).map(|i| (i % 251) as u8).collect();
+    let rle = compress(&pixels, Depth::Rgb888);
+
+    assert_eq!(rle.len(), pixels.len() + SIZE.div_ceil(128) as usize);
+    assert!(rle.len() <= Limits::default().image);
+    assert_eq!(
+        decompress(&rle, Depth::Rgb888, pixels.len()).unwrap(),
+        pixels
+    );
+}
+
+#[test]
+fn runs() {
+    let pixels = [[7; 200].as_slice(), &[1, 2, 3], &[9; 3], &[4, 5, 5]].concat();
+    let rle = compress(&pixels, Depth::Rgb332);
+
+    // 129 and then 71 sevens, the literals, the nines, and the rest, with its short run kept inline.
+    assert_eq!(
+        &rle[..],
+        [0xff, 7, 0xc5, 7, 0x02, 1, 2, 3, 0x81, 9, 0x02, 4, 5, 5]
+    );
+    assert_eq!(
+        decompress(&rle, Depth::Rgb332, pixels.len()).unwrap(),
+        &pixels[..]
+    );
+}
+
+#[test]
+fn rejects_malformed() {
+    // Cut off in the middle of the literal pixels.
+    assert!(decompress(&[0x03, 1, 2], Depth::Rgb332, 4).is_err());
+    // A run that goes past the end of the image.
+    assert!(decompress(&[0x90, 1], Depth::Rgb332, 4).is_err());
+    // Not enough pixels to fill it.
+    assert!(decompress(&[0x81, 1], Depth::Rgb332, 4).is_err());
+    // Half of a pixel.
+    assert!(decompress(&[0x80, 1, 2], Depth::Rgb888, 6).is_err());
+}
+
+/// How far off the decoded slices are on average, per channel. Blurring both first shows how
+/// close it looks from a distance, which is what dithering is for.
+fn error(depth: Depth, dither: Dither, blur: bool) -> f64 {
+    let mut total = 0;
+    for slice in SLICES.iter() {
+        let bytes = encode(slice, depth, dither).unwrap();
+        assert_eq!(bytes.len(), depth.size(WIDTH, HEIGHT) as usize);
+        let decoded = decode(&bytes, WIDTH, HEIGHT, depth).unwrap();
+
+        let (decoded, original) = match blur {
+            true => (imageops::blur(&decoded, 1.5), imageops::blur(slice, 1.5)),
+            false => (decoded, slice.clone()),
+        };
+        for (a, b) in decoded.pixels().zip(original.pixels()) {
+            total += (0..3).map(|c| a[c].abs_diff(b[c]) as u64).sum::<u64>();
+        }
+    }
+
+    let error = total as f64 / (SLICES.len() as u32 * SIZE * 3) as f64;
+    eprintln!("{depth:?}, {dither:?}, blurred: {blur}: {error:.2}");
+    error
+}
+
+#[test]
+fn dithering_hides_banding() {
+    let banded = error(Depth::Rgb332, Dither::None, true);
+    assert!(error(Depth::Rgb332, Dither::FloydSteinberg, true) < banded / 4.0);
+    assert!(error(Depth::Rgb332, Dither::Ordered, true) < banded / 4.0);
+}
+
+#[test]
+fn palette_is_closer() {
+    // Picking colors for the image gets a lot closer than the same colors for everything.
+    let fixed = error(Depth::Rgb332, Dither::None, false);
+    assert!(error(Depth::Indexed, Dither::None, false) < fixed / 4.0);
+    assert!(error(Depth::Indexed, Dither::FloydSteinberg, true) < fixed / 4.0);
+}
+
+#[test]
+fn palette_comes_first() {
+    // Two colors which aren't anywhere in `Rgb332`, so they can only come from the palette.
+    let image = RgbImage::from_fn(4, 2, |x, _| match x % 2 {
+        0 => Rgb([10, 20, 30]),
+        _ => Rgb([200, 100, 50]),
+    });
+
+    let bytes = encode(&image, Depth::Indexed, Dither::None).unwrap();
+    assert_eq!(bytes.len(), 768 + 8);
+    let decoded = decode(&bytes, 4, 2, Depth::Indexed).unwrap();
+    for (a, b) in decoded.pixels().zip(image.pixels()) {
+        assert!(
+            (0..3).all(|c| a[c].abs_diff(b[c]) <= 4),
+            "{a:?} isn't {b:?}"
+        );
+    }
+}
+
+#[test]
+fn palette_counts_towards_payload() {
+    let display = Display {
+        depth: Depth::Indexed,
+        max_payload: 768 + 320 * 240 - 1,
+        ..Display::default()
+    };
+    assert!(display.size() <= display.max_payload);
+    assert!(display.dimensions().0 < WIDTH);
+}
+
+#[test]
+fn rejects_bad_images() {
+    assert_eq!(len(0, 16, Depth::Rgb332), Err(Error::Dimensions(0, 16)));
+    assert_eq!(
+        len(1 << 16, 1, Depth::Rgb888),
+        Err(Error::Dimensions(1 << 16, 1))
+    );
+    assert_eq!(
+        len(u16::MAX as u32, u16::MAX as u32, Depth::Rgb888),
+        Ok(3 * 65535 * 65535)
+    );
+
+    // A pixel short, and a palette without any pixels after it.
+    assert_eq!(decode(&[0; 7], 4, 2, Depth::Rgb332), Err(Error::Size(8, 7)));
+    assert_eq!(
+        decode(&[0; 768], 4, 2, Depth::Indexed),
+        Err(Error::Size(776, 768))
+    );
+    assert_eq!(
+        encode(&RgbImage::new(0, 0), Depth::Rgb332, Dither::None),
+        Err(Error::Dimensions(0, 0))
+    );
+}
+
+#[test]
+fn passes_cover_every_row() {
+    for height in [1, 2, 5, 9, HEIGHT] {
+        let depth = Depth::Indexed;
+        let image = imageops::resize(&SLICES[0], WIDTH, height, imageops::FilterType::Nearest);
+        let pixels = encode(&image, depth, Dither::None).unwrap();
+        let (palette, passes) = interlace(&pixels, WIDTH, height, depth).unwrap();
+
+        // Putting the rows back where they go has to give back the whole image.
+        let mut rebuilt = vec![None; (WIDTH * height) as usize];
+        for pass in &passes {
+            let rows = pass.rows.chunks(WIDTH as usize);
+            for (i, row) in rows.enumerate() {
+                let y = pass.first as usize + i * pass.step as usize;
+                for (x, pixel) in row.iter().enumerate() {
+                    let slot = &mut rebuilt[y * WIDTH as usize + x];
+                    assert!(slot.is_none(), "row {y} is in more than one pass");
+                    *slot = Some(*pixel);
+                }
+            }
+        }
+
+        let rebuilt: Vec<u8> = rebuilt.into_iter().map(Option::unwrap).collect();
+        assert_eq!([&palette[..], &rebuilt].concat(), pixels);
+        assert!(passes.iter().all(|x| !x.rows.is_empty()));
+    }
+
+    assert_eq!(
+        interlace(&[0; 7], 4, 2, Depth::Rgb332),
+        Err(Error::Size(8, 7))
+    );
+}
@@ -0,0 +1,220 @@
+use std::{net::SocketAddrV4, time::Duration};
+
+use shared::{Capabilities, Packet};
+use tokio::{
+    select,
+    time::{Instant, sleep_until},
+};
+use tokio_serial::SerialPortBuilderExt;
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::{
+    discovery::Candidate,
+    serial::{Codec, Serial},
+    tcp::TCP,
+};
+
+/// How long to wait before the first attempt at reconnecting to the server.
+const MIN_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The longest to ever wait between attempts, however long the server has been gone.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Everything needed to connect a calculator to the server, which is the same for all of them.
+#[derive(Clone, Copy, Debug)]
+pub struct Bridge {
+    pub address: SocketAddrV4,
+    pub baud: u32,
+    pub codec: Codec,
+}
+
+/// What the server knows about a calculator, so that it can be told again after reconnecting.
+#[derive(Debug, Default)]
+struct Session {
+    /// The last `INIT` the calculator sent.
+    init: Option<Packet>,
+
+    /// The token from the last `CONFIRMED`, if the server supports resuming with it.
+    token: Option<u64>,
+
+    /// Whether the server is answering a `RESUME` which the transponder sent on its own.
+    resuming: bool,
+}
+
+impl Session {
+    /// Keeps track of a packet on its way to the server. Resuming is up to the transponder rather
+    /// than the calculator, so it's what asks for `RESUME` in the calculator's `INIT`.
+    fn sent(&mut self, packet: &mut Packet) {
+        match packet {
+            Packet::Init { capabilities, .. } => {
+                *capabilities = *capabilities | Capabilities::RESUME;
+                *self = Self {
+                    init: Some(packet.clone()),
+                    ..Self::default()
+                }
+            }
+            Packet::SoftQuit => *self = Self::default(),
+            _ => {}
+        }
+    }
+
+    /// Keeps track of a packet on its way to the calculator, and returns what to send
+    /// the server instead if it shouldn't be passed along.
+    fn received(&mut self, packet: &Packet) -> Option<Packet> {
+        match packet {
+            Packet::Confirmed {
+                capabilities,
+                token,
+                ..
+            } => {
+                self.resuming = false;
+                self.token = capabilities
+                    .contains(Capabilities::RESUME)
+                    .then_some(*token);
+            }
+            // The calculator never asked to resume, so it doesn't need to know that it failed.
+            Packet::ResumeFailed if self.resuming => {
+                self.resuming = false;
+                self.token = None;
+                return self.init.clone();
+            }
+            // Being removed by the server isn't something that reconnecting would fix.
+            Packet::Error { .. } => *self = Self::default(),
+            _ => {}
+        }
+
+        None
+    }
+
+    /// The first packet to send after reconnecting, to pick up where the calculator left off.
+    fn handshake(&mut self) -> Option<Packet> {
+        self.init.as_ref()?;
+        match self.token {
+            Some(token) => {
+                self.resuming = true;
+                Some(Packet::Resume { token })
+            }
+            None => self.init.clone(),
+        }
+    }
+
+    /// Whether the calculator said that it wants to hear about the server going away.
+    fn reconnect(&self) -> bool {
+        matches!(
+            self.init,
+            Some(Packet::Init { capabilities, .. }) if capabilities.contains(Capabilities::RECONNECT)
+        )
+    }
+}
+
+impl Bridge {
+    /// Gives a calculator its own connection to the server until it's unplugged,
+    /// reconnecting with a growing delay whenever the server goes away.
+    pub async fn run(self, candidate: Candidate) -> eyre::Result<()> {
+        let (reader, writer) = tokio_serial::new(&candidate.path, self.baud)
+            .open_native_async()
+            .map(tokio::io::split)?;
+        let mut serial = Serial {
+            path: candidate.path.clone(),
+            reader: FramedRead::new(reader, self.codec),
+            writer: FramedWrite::new(writer, self.codec),
+        };
+
+        let mut session = Session::default();
+        let mut tcp: Option<TCP> = None;
+        let mut backoff = MIN_BACKOFF;
+        let mut retry = Instant::now();
+
+        let result = loop {
+            select! {
+                packet = serial.next() => {
+                    let mut packet = match packet {
+                        Ok(Some(packet)) => packet,
+                        Ok(None) => break Ok(()),
+                        Err(error) => break Err(error),
+                    };
+
+                    session.sent(&mut packet);
+                    let Some(server) = &mut tcp else {
+                        eprintln!(
+                            "transponder(serial): {}: dropped a packet while reconnecting",
+                            serial.path
+                        );
+                        continue;
+                    };
+
+                    if let Err(error) = server.send(packet).await {
+                        eprintln!("transponder(tcp): {}: {error}", serial.path);
+                        tcp = None;
+                    }
+                }
+                packet = async { tcp.as_mut()?.next().await }, if tcp.is_some() => match packet {
+                    None => tcp = None,
+                    Some(packet) => match (session.received(&packet), &mut tcp) {
+                        (Some(reply), Some(server)) => {
+                            if let Err(error) = server.send(reply).await {
+                                eprintln!("transponder(tcp): {}: {error}", serial.path);
+                                tcp = None;
+                            }
+                        }
+                        (Some(_), None) => {}
+                        (None, _) => serial.send(packet).await?,
+                    },
+                },
+                () = sleep_until(retry), if tcp.is_none() => {
+                    let connected = async {
+                        let mut server = TCP::connect(candidate.path.clone(), self.address).await?;
+                        if let Some(packet) = session.handshake() {
+                            server.send(packet).await?;
+                        }
+
+                        eyre::Ok(server)
+                    };
+
+                    match connected.await {
+                        Ok(server) => {
+                            eprintln!("transponder: connected {candidate} to {}", self.address);
+                            tcp = Some(server);
+                            backoff = MIN_BACKOFF;
+                        }
+                        Err(error) => {
+                            eprintln!(
+                                "transponder(tcp): {}: retrying in {backoff:?}: {error}",
+                                serial.path
+                            );
+                            retry = Instant::now() + backoff;
+                            backoff = (backoff * 2).min(MAX_BACKOFF);
+                        }
+                    }
+
+                    continue;
+                }
+            }
+
+            // Either end of the loop can notice that the server went away.
+            if tcp.is_none() {
+                eprintln!(
+                    "transponder(tcp): {}: lost connection to the server",
+                    serial.path
+                );
+                if session.reconnect() {
+                    serial.send(Packet::Disconnected).await?;
+                }
+
+                retry = Instant::now() + backoff;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        };
+
+        // The calculator was unplugged, so the player shouldn't stick around either.
+        eprintln!(
+            "transponder(serial): {}: lost serial connection",
+            serial.path
+        );
+        if let Some(server) = &mut tcp {
+            server.send(Packet::SoftQuit).await?;
+        }
+
+        result
+    }
+}
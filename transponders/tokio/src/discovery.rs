@@ -0,0 +1,170 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use eyre::eyre;
+use tokio_serial::{SerialPortType, UsbPortInfo};
+
+/// A USB vendor id, optionally along with a product id, written like `0451:e008`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UsbId {
+    pub vid: u16,
+    pub pid: Option<u16>,
+}
+
+impl UsbId {
+    fn matches(self, info: &UsbPortInfo) -> bool {
+        info.vid == self.vid && self.pid.is_none_or(|x| x == info.pid)
+    }
+}
+
+impl FromStr for UsbId {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = |x: &str| {
+            u16::from_str_radix(x, 16).map_err(|_| eyre!("{x:?} isn't a hexadecimal usb id"))
+        };
+
+        match s.split_once(':') {
+            Some((vid, pid)) => Ok(Self {
+                vid: hex(vid)?,
+                pid: Some(hex(pid)?),
+            }),
+            None => Ok(Self {
+                vid: hex(s)?,
+                pid: None,
+            }),
+        }
+    }
+}
+
+/// A serial port which a calculator might be plugged into.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub path: String,
+    pub usb: Option<UsbPortInfo>,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+
+        let Some(usb) = &self.usb else {
+            return Ok(());
+        };
+
+        write!(f, " ({:04x}:{:04x}", usb.vid, usb.pid)?;
+        for name in [&usb.manufacturer, &usb.product].into_iter().flatten() {
+            write!(f, " {name}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Where to look for serial devices.
+#[derive(Clone, Debug)]
+pub struct Discovery {
+    /// A specific device to use, instead of looking for one.
+    pub device: Option<String>,
+
+    /// Globs which device paths are matched against, where `*` is any amount of
+    /// characters and `?` is exactly one.
+    pub patterns: Vec<String>,
+
+    /// Only uses USB devices with this id.
+    pub usb: Option<UsbId>,
+}
+
+/// Matches a path against a glob, which only supports `*` and `?`.
+fn glob(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+
+    // Where to backtrack to if the rest doesn't match: just past the last `*`,
+    // and the part of the path which it would cover one more character of.
+    let mut star = None;
+    let (mut p, mut i) = (0, 0);
+    while i < path.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, i));
+                p += 1;
+            }
+            Some(&x) if x == '?' || x == path[i] => {
+                p += 1;
+                i += 1;
+            }
+            _ => match star {
+                Some((after, covered)) => {
+                    p = after;
+                    i = covered + 1;
+                    star = Some((after, covered + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|x| *x == '*')
+}
+
+impl Discovery {
+    /// Every serial device which is plugged in right now and fits the criteria, sorted by path.
+    pub fn candidates(&self) -> Vec<Candidate> {
+        let ports = tokio_serial::available_ports().unwrap_or_default();
+        let usb = |path: &str| {
+            ports
+                .iter()
+                .find(|x| x.port_name == path)
+                .and_then(|x| match &x.port_type {
+                    SerialPortType::UsbPort(info) => Some(info.clone()),
+                    _ => None,
+                })
+        };
+
+        if let Some(device) = &self.device {
+            if !Path::new(device).exists() {
+                return Vec::new();
+            }
+
+            return vec![Candidate {
+                path: device.clone(),
+                usb: usb(device),
+            }];
+        }
+
+        // Not every device shows up as a port, like the `cu.*` side of them on macOS,
+        // so the directories which the patterns point into are looked through as well.
+        let mut paths: Vec<String> = ports.iter().map(|x| x.port_name.clone()).collect();
+        for pattern in &self.patterns {
+            let wildcard = pattern.find(['*', '?']).unwrap_or(pattern.len());
+            let Some(slash) = pattern[..wildcard].rfind('/') else {
+                continue;
+            };
+
+            let Ok(entries) = std::fs::read_dir(&pattern[..slash.max(1)]) else {
+                continue;
+            };
+            paths.extend(
+                entries
+                    .filter_map(|x| x.ok())
+                    .filter_map(|x| x.path().to_str().map(|x| x.to_owned())),
+            );
+        }
+        paths.sort();
+        paths.dedup();
+
+        paths
+            .into_iter()
+            .filter(|x| self.patterns.iter().any(|pattern| glob(pattern, x)))
+            .map(|path| Candidate {
+                usb: usb(&path),
+                path,
+            })
+            .filter(|x| match (self.usb, &x.usb) {
+                (None, _) => true,
+                (Some(id), Some(info)) => id.matches(info),
+                (Some(_), None) => false,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,74 @@
+//! A pretend calculator on the other end of a pseudo-terminal, so that the whole path from
+//! the server through a transponder to a "calculator" can be tested without any hardware.
+
+use eyre::eyre;
+use futures::{SinkExt, StreamExt};
+use shared::Packet;
+use tokio::io::AsyncWriteExt;
+use tokio_serial::{SerialPort, SerialStream};
+use tokio_util::codec::Framed;
+
+use crate::{discovery::Discovery, serial::Codec};
+
+/// Drives the calculator's side of a serial link with scripted packets.
+///
+/// The transponder opens [`path`](Self::path) like it would a real device, and unplugging the
+/// calculator is just dropping it.
+pub struct Calculator {
+    path: String,
+    stream: Framed<SerialStream, Codec>,
+
+    // The transponder's side is kept open too, so that nothing that's sent before it opens
+    // the device gets lost, and so the link doesn't look unplugged until this is dropped.
+    _device: SerialStream,
+}
+
+impl Calculator {
+    /// Plugs in a new calculator, which talks to the transponder with `codec`.
+    pub fn new(codec: Codec) -> eyre::Result<Self> {
+        let (stream, device) = SerialStream::pair()?;
+        let path = device
+            .name()
+            .ok_or_else(|| eyre!("pseudo-terminal doesn't have a path"))?;
+
+        Ok(Self {
+            path,
+            stream: Framed::new(stream, codec),
+            _device: device,
+        })
+    }
+
+    /// The path of the device which the transponder should open.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Points discovery at just this calculator.
+    pub fn discovery(&self) -> Discovery {
+        Discovery {
+            device: Some(self.path.clone()),
+            patterns: Vec::new(),
+            usb: None,
+        }
+    }
+
+    /// Sends a packet to the transponder.
+    pub async fn send(&mut self, packet: Packet) -> eyre::Result<()> {
+        self.stream.send(packet).await?;
+        Ok(())
+    }
+
+    /// Sends bytes as they are, like line noise or a packet that was cut off.
+    pub async fn send_raw(&mut self, bytes: &[u8]) -> eyre::Result<()> {
+        self.stream.get_mut().write_all(bytes).await?;
+        Ok(())
+    }
+
+    /// Waits for the next packet from the transponder.
+    pub async fn recv(&mut self) -> eyre::Result<Packet> {
+        match self.stream.next().await {
+            Some(frame) => Ok(frame??),
+            None => Err(eyre!("transponder closed the serial link")),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! Bridges calculators plugged in over serial to the server, each with its own connection.
+
+pub mod bridge;
+pub mod discovery;
+#[cfg(unix)]
+pub mod emulator;
+pub mod serial;
+pub mod tcp;
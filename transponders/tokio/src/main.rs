@@ -1,48 +1,108 @@
 use std::{
+    collections::HashMap,
     net::{Ipv4Addr, SocketAddrV4},
     str::FromStr,
+    time::Duration,
 };
 
-use crate::{serial::Serial, tcp::TCP};
 use clap::Parser;
-use shared::PORT;
-use tokio::{io, select, sync::mpsc};
-use tokio_serial::SerialStream;
-
-pub mod serial;
-pub mod tcp;
+use shared::{
+    PORT,
+    framed::{FrameCodec, PacketCodec},
+};
+use tokio::{task::JoinHandle, time::sleep};
+use tokio_transponder::{
+    bridge::Bridge,
+    discovery::{Discovery, UsbId},
+    serial::Codec,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     #[arg(short, long, default_value = "127.0.0.1")]
     address: String,
-}
 
-pub enum Message {
-    Serial(io::WriteHalf<SerialStream>),
+    /// Wraps packets on the serial link in checksummed frames, for calculators built with `FRAMED=1`.
+    #[arg(short, long)]
+    framed: bool,
+
+    /// A specific serial device to use, instead of looking for one.
+    #[arg(short, long, conflicts_with = "pattern")]
+    device: Option<String>,
+
+    /// Globs to look for serial devices with, where `*` matches anything and `?` one character.
+    #[arg(short, long, default_values = ["/dev/cu.usbmodem*", "/dev/ttyACM*"])]
+    pattern: Vec<String>,
+
+    /// Only uses USB serial devices with this vendor id, and optionally product id, in hex.
+    #[arg(short, long, value_name = "VID[:PID]")]
+    usb: Option<UsbId>,
+
+    /// Baud rate of the serial link, which has to match the calculator's.
+    #[arg(short, long, default_value_t = 9600 * 4)]
+    baud: u32,
+
+    /// Prints the serial devices which would be used, and exits.
+    #[arg(short, long)]
+    list: bool,
 }
 
+/// How often to look for newly plugged in calculators.
+const SCAN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long to wait before trying a calculator again after its bridge failed.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> eyre::Result<()> {
     let args = Args::parse();
-    let (tx, rx) = mpsc::channel(8);
+    let discovery = Discovery {
+        device: args.device,
+        patterns: args.pattern,
+        usb: args.usb,
+    };
 
-    let address = SocketAddrV4::new(Ipv4Addr::from_str(&args.address)?, PORT);
-    let (tcp, writer) = TCP::init(rx, address.clone()).await?;
-    let serial = Serial::new(writer, tx).await;
+    if args.list {
+        for candidate in discovery.candidates() {
+            println!("{candidate}");
+        }
+
+        return Ok(());
+    }
 
-    let serial_handle = tokio::spawn(serial.listen());
-    let tcp_handle = tokio::spawn(tcp.listen(address));
+    let codec = if args.framed {
+        Codec::Framed(FrameCodec::default())
+    } else {
+        Codec::Raw(PacketCodec::default())
+    };
+    let bridge = Bridge {
+        address: SocketAddrV4::new(Ipv4Addr::from_str(&args.address)?, PORT),
+        baud: args.baud,
+        codec,
+    };
 
-    select! {
-        result = serial_handle => {
-            eprintln!("transponder: serial quit: {result:?}");
-        },
-        result = tcp_handle => {
-            eprintln!("transponder: tcp quit: {result:?}");
+    // Every calculator gets its own bridge, keyed by the path of its serial device.
+    let mut bridges: HashMap<String, JoinHandle<()>> = HashMap::new();
+    loop {
+        bridges.retain(|_, x| !x.is_finished());
+
+        for candidate in discovery.candidates() {
+            if bridges.contains_key(&candidate.path) {
+                continue;
+            }
+
+            let path = candidate.path.clone();
+            let handle = tokio::spawn(async move {
+                let path = candidate.path.clone();
+                if let Err(error) = bridge.run(candidate).await {
+                    eprintln!("transponder: {path}: {error:?}");
+                    sleep(RETRY_DELAY).await;
+                }
+            });
+            bridges.insert(path, handle);
         }
-    }
 
-    Ok(())
+        sleep(SCAN_INTERVAL).await;
+    }
 }
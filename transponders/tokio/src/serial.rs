@@ -1,69 +1,91 @@
-use std::time::Duration;
+use bytes::BytesMut;
+use futures::{SinkExt, StreamExt};
+use shared::{
+    Packet,
+    framed::{FrameCodec, PacketCodec},
+};
+use tokio::io::{ReadHalf, WriteHalf};
+use tokio_serial::SerialStream;
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
 
-use shared::{Packet, PacketWriteExt, deserializers::Deserialize};
-use tokio::{io::ReadHalf, sync::mpsc::Sender, time::sleep};
-use tokio_serial::{SerialPortBuilderExt, SerialStream};
+/// How packets are sent over the serial link, which has to match what the calculator was built with.
+#[derive(Clone, Copy, Debug)]
+pub enum Codec {
+    /// The raw protocol, which can't recover from a corrupted byte.
+    Raw(PacketCodec),
 
-use crate::Message;
+    /// Checksummed frames, which corrupted packets are dropped from.
+    Framed(FrameCodec),
+}
+
+impl Decoder for Codec {
+    type Item = Result<Packet, shared::Error>;
+    type Error = shared::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self {
+            Codec::Raw(codec) => Ok(codec.decode(src)?.map(Ok)),
+            Codec::Framed(codec) => codec.decode(src),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self {
+            Codec::Raw(codec) => Ok(codec.decode_eof(src)?.map(Ok)),
+            Codec::Framed(codec) => codec.decode_eof(src),
+        }
+    }
+}
 
+impl Encoder<Packet> for Codec {
+    type Error = shared::Error;
+
+    fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        match self {
+            Codec::Raw(codec) => codec.encode(item, dst),
+            Codec::Framed(codec) => codec.encode(item, dst),
+        }
+    }
+}
+
+/// The calculator's end of a bridge.
 pub struct Serial {
-    pub(crate) writer: shared::Writer,
-    pub(crate) tx: Sender<Message>,
+    pub(crate) path: String,
+    pub(crate) reader: FramedRead<ReadHalf<SerialStream>, Codec>,
+    pub(crate) writer: FramedWrite<WriteHalf<SerialStream>, Codec>,
 }
 
 impl Serial {
-    pub async fn connect(&mut self, mut reader: ReadHalf<SerialStream>) -> eyre::Result<()> {
-        loop {
-            match Packet::deserialize(&mut reader).await {
+    /// Waits for the next packet from the calculator, or [`None`] once it's unplugged.
+    ///
+    /// This is cancel safe, so that the server can be listened to at the same time.
+    pub async fn next(&mut self) -> eyre::Result<Option<Packet>> {
+        while let Some(frame) = self.reader.next().await {
+            // Only the raw protocol ends up here on a bad packet, since it can't tell
+            // where the next one starts. Frames are just skipped over instead.
+            match frame? {
                 Ok(packet) => {
-                    eprintln!("transponder(serial): server-bound serial packet: {packet:?}");
-                    self.writer.write_packet(packet).await?;
+                    eprintln!(
+                        "transponder(serial): {}: server-bound serial packet: {packet:?}",
+                        self.path
+                    );
+                    return Ok(Some(packet));
                 }
                 Err(error) => {
                     eprintln!(
-                        "transponder(serial): error parsing server-bound serial packet: {error:?}"
+                        "transponder(serial): {}: dropped a corrupted frame: {error}",
+                        self.path
                     );
-                    // TODO: Don't just quit like this, weakling.
-                    break Ok(());
                 }
             }
         }
-    }
-
-    pub async fn listen(mut self) -> eyre::Result<()> {
-        loop {
-            let files: Vec<_> = std::fs::read_dir("/dev")
-                .unwrap()
-                .filter_map(|e| e.ok())
-                .filter_map(|e| e.path().to_str().map(|s| s.to_string()))
-                .filter(|p| p.starts_with("/dev/cu.usbmodem"))
-                .collect();
 
-            if files.is_empty() {
-                sleep(Duration::from_millis(50)).await;
-                continue;
-            }
-
-            let path = &files[0];
-            let Ok((reader, writer)) = tokio_serial::new(path, 9600 * 4)
-                .open_native_async()
-                .and_then(|x| Ok(tokio::io::split(x)))
-            else {
-                continue;
-            };
-            eprintln!("transponder(serial): new serial connection: {path}");
-            self.tx.send(Message::Serial(writer)).await?;
-
-            let result = self.connect(reader).await;
-            self.writer.write_packet(Packet::SoftQuit).await?;
-            eprintln!("transponder(serial): lost serial connection");
-            if let Err(error) = result {
-                eprintln!("-> {error:?}");
-            }
-        }
+        Ok(None)
     }
 
-    pub async fn new(writer: shared::Writer, tx: Sender<Message>) -> Self {
-        Self { writer, tx }
+    /// Sends a packet to the calculator.
+    pub async fn send(&mut self, packet: Packet) -> eyre::Result<()> {
+        self.writer.send(packet).await?;
+        Ok(())
     }
 }
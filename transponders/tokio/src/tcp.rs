@@ -1,62 +1,58 @@
 use std::net::SocketAddrV4;
 
-use shared::{BufferedSplitExt, Packet, PacketReadExt, serializers::Serialize};
+use futures::StreamExt;
+use shared::{Packet, PacketWriteExt, framed::PacketCodec};
 use tokio::{
-    net::TcpStream,
-    select,
-    sync::mpsc::{self, Receiver},
+    io::BufWriter,
+    net::{TcpStream, tcp::OwnedReadHalf},
 };
+use tokio_util::codec::FramedRead;
 
-use crate::Message;
-
+/// The server's end of a bridge, which is replaced whenever it has to reconnect.
 pub struct TCP {
-    pub(crate) reader: shared::Reader,
-    pub(crate) rx: Receiver<Message>,
+    pub(crate) path: String,
+    pub(crate) reader: FramedRead<OwnedReadHalf, PacketCodec>,
+    pub(crate) writer: shared::Writer,
 }
 
 impl TCP {
-    pub async fn listen(mut self, address: SocketAddrV4) -> eyre::Result<()> {
-        eprintln!("transponder(tcp): connected to {address}");
-        let mut writer = None;
+    pub async fn connect(path: String, address: SocketAddrV4) -> eyre::Result<Self> {
+        let (reader, writer) = TcpStream::connect(address).await?.into_split();
+
+        Ok(Self {
+            path,
+            reader: FramedRead::new(reader, PacketCodec::default()),
+            writer: BufWriter::new(writer),
+        })
+    }
 
-        loop {
-            while writer.is_none() {
-                let message = self.rx.recv().await.unwrap();
-                match message {
-                    Message::Serial(serial) => writer = Some(serial),
+    /// Waits for the next packet from the server, or [`None`] once the connection is gone.
+    ///
+    /// This is cancel safe, so that the calculator can be listened to at the same time.
+    pub async fn next(&mut self) -> Option<Packet> {
+        match self.reader.next().await? {
+            Ok(packet) => {
+                if matches!(&packet, Packet::Round { .. }) {
+                    eprintln!("transponder(tcp): {}: round packet", self.path);
+                } else {
+                    eprintln!(
+                        "transponder(tcp): {}: client-bound tcp packet: {packet:?}",
+                        self.path
+                    );
                 }
-            }
-
-            let packet = select! {
-                Some(Message::Serial(serial)) = self.rx.recv() => {
-                    writer = Some(serial);
-                    continue;
-                },
-                packet = self.reader.read_packet() => packet?,
-            };
 
-            if matches!(&packet, Packet::Round { .. }) {
-                eprintln!("transponder(tcp): round packet");
-            } else {
-                eprintln!("transponder(tcp): client-bound tcp packet: {packet:?}");
+                Some(packet)
+            }
+            Err(error) => {
+                eprintln!("transponder(tcp): {}: {error}", self.path);
+                None
             }
-
-            let Err(error) = packet.serialize(&mut writer.as_mut().unwrap()).await else {
-                continue;
-            };
-
-            eprintln!("transponder: error serializing: {error:?}");
-            writer = None;
         }
     }
 
-    pub async fn init(
-        rx: mpsc::Receiver<Message>,
-        address: SocketAddrV4,
-    ) -> eyre::Result<(Self, shared::Writer)> {
-        let tcp = TcpStream::connect(address).await?;
-        let (reader, writer) = tcp.buffered_split();
-
-        Ok((Self { reader, rx }, writer))
+    /// Sends a packet to the server.
+    pub async fn send(&mut self, packet: Packet) -> eyre::Result<()> {
+        self.writer.write_packet(packet).await?;
+        Ok(())
     }
 }
@@ -0,0 +1,193 @@
+//! Runs a bridge between an emulated calculator and a scripted server, to check the
+//! transponder end to end without any hardware.
+#![cfg(unix)]
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    time::Duration,
+};
+
+use shared::{
+    Capabilities, ClientOptions, Color, Display, Packet, PacketReadExt, PacketWriteExt,
+    framed::{FrameCodec, PacketCodec},
+    lobby::Clients,
+    serial::SYNC,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+    time::timeout,
+};
+use tokio_transponder::{bridge::Bridge, emulator::Calculator, serial::Codec};
+
+/// Longer than anything should ever take, even with the bridge backing off before reconnecting.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const TOKEN: u64 = 0x0123_4567_89ab_cdef;
+
+fn options() -> ClientOptions {
+    ClientOptions {
+        color: Color::Cyan,
+        user: String::from("calc"),
+    }
+}
+
+fn init() -> Packet {
+    Packet::Init {
+        capabilities: Capabilities::LOOK | Capabilities::RECONNECT,
+        options: options(),
+        display: Display::default(),
+    }
+}
+
+/// The calculator's `INIT` as the server gets it, asking to resume since the bridge takes care of that.
+fn forwarded() -> Packet {
+    Packet::Init {
+        capabilities: Capabilities::LOOK | Capabilities::RECONNECT | Capabilities::RESUME,
+        options: options(),
+        display: Display::default(),
+    }
+}
+
+fn confirmed(token: u64) -> Packet {
+    Packet::Confirmed {
+        capabilities: Capabilities::ALL,
+        id: 0,
+        token,
+        options: options(),
+        lobby: Clients::default(),
+    }
+}
+
+/// Stands in for the server, with every connection handled by the test itself.
+struct Server {
+    listener: TcpListener,
+}
+
+impl Server {
+    async fn new() -> Self {
+        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        Self { listener }
+    }
+
+    fn address(&self) -> SocketAddrV4 {
+        match self.listener.local_addr().unwrap() {
+            SocketAddr::V4(address) => address,
+            SocketAddr::V6(_) => unreachable!(),
+        }
+    }
+
+    async fn accept(&self) -> TcpStream {
+        timeout(TIMEOUT, self.listener.accept())
+            .await
+            .unwrap()
+            .unwrap()
+            .0
+    }
+}
+
+async fn read(stream: &mut TcpStream) -> Packet {
+    timeout(TIMEOUT, stream.read_packet())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+async fn recv(calculator: &mut Calculator) -> Packet {
+    timeout(TIMEOUT, calculator.recv()).await.unwrap().unwrap()
+}
+
+/// Plugs a new calculator into a bridge that connects it to `server`.
+fn plug_in(server: &Server, codec: Codec) -> (Calculator, JoinHandle<eyre::Result<()>>) {
+    let calculator = Calculator::new(codec).unwrap();
+    let candidate = calculator.discovery().candidates().remove(0);
+    assert_eq!(candidate.path, calculator.path());
+
+    let bridge = Bridge {
+        address: server.address(),
+        baud: 9600,
+        codec,
+    };
+    (calculator, tokio::spawn(bridge.run(candidate)))
+}
+
+async fn forwards(codec: Codec) {
+    let server = Server::new().await;
+    let (mut calculator, _bridge) = plug_in(&server, codec);
+    let mut stream = server.accept().await;
+
+    calculator.send(init()).await.unwrap();
+    assert_eq!(read(&mut stream).await, forwarded());
+
+    stream.write_packet(confirmed(TOKEN)).await.unwrap();
+    assert_eq!(recv(&mut calculator).await, confirmed(TOKEN));
+}
+
+#[tokio::test]
+async fn forwards_raw() {
+    forwards(Codec::Raw(PacketCodec::default())).await;
+}
+
+#[tokio::test]
+async fn forwards_framed() {
+    forwards(Codec::Framed(FrameCodec::default())).await;
+}
+
+#[tokio::test]
+async fn skips_corrupted_frames() {
+    let server = Server::new().await;
+    let codec = Codec::Framed(FrameCodec::default());
+    let (mut calculator, _bridge) = plug_in(&server, codec);
+    let mut stream = server.accept().await;
+
+    // Line noise, and then the start of a frame which never gets finished.
+    calculator.send_raw(&[0x00, 0xff, 0x13]).await.unwrap();
+    calculator.send_raw(&SYNC).await.unwrap();
+    calculator.send_raw(&[0, 0, 0, 4, 1, 2]).await.unwrap();
+    calculator.send(init()).await.unwrap();
+    assert_eq!(read(&mut stream).await, forwarded());
+}
+
+#[tokio::test]
+async fn reconnects() {
+    let server = Server::new().await;
+    let (mut calculator, _bridge) = plug_in(&server, Codec::Raw(PacketCodec::default()));
+    let mut stream = server.accept().await;
+
+    calculator.send(init()).await.unwrap();
+    assert_eq!(read(&mut stream).await, forwarded());
+    stream.write_packet(confirmed(TOKEN)).await.unwrap();
+    assert_eq!(recv(&mut calculator).await, confirmed(TOKEN));
+
+    // The server restarts, and doesn't remember the session anymore.
+    drop(stream);
+    assert_eq!(recv(&mut calculator).await, Packet::Disconnected);
+
+    let mut stream = server.accept().await;
+    assert_eq!(read(&mut stream).await, Packet::Resume { token: TOKEN });
+    stream.write_packet(Packet::ResumeFailed).await.unwrap();
+    assert_eq!(read(&mut stream).await, forwarded());
+
+    // The calculator never finds out that resuming failed, just that it's back.
+    stream.write_packet(confirmed(TOKEN + 1)).await.unwrap();
+    assert_eq!(recv(&mut calculator).await, confirmed(TOKEN + 1));
+}
+
+#[tokio::test]
+async fn unplugged() {
+    let server = Server::new().await;
+    let (mut calculator, bridge) = plug_in(&server, Codec::Raw(PacketCodec::default()));
+    let mut stream = server.accept().await;
+
+    calculator.send(init()).await.unwrap();
+    assert_eq!(read(&mut stream).await, forwarded());
+
+    drop(calculator);
+    assert_eq!(read(&mut stream).await, Packet::SoftQuit);
+
+    // Whether reading from a pseudo-terminal with nothing on the other end is an error
+    // or just the end of it depends on the platform, but either way the bridge is done.
+    let _ = timeout(TIMEOUT, bridge).await.unwrap().unwrap();
+}
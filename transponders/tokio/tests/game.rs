@@ -0,0 +1,120 @@
+//! Runs a bridge between an emulated calculator and the real server, to check that a calculator
+//! can get all the way from plugging in to playing a round.
+#![cfg(unix)]
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::Path,
+    time::Duration,
+};
+
+use server::{
+    images::{Provider, bundle::Bundle},
+    prefetch::Prefetch,
+    server::{Config, Server},
+};
+use shared::{
+    BufferedSplitExt, Capabilities, ClientOptions, Color, Display, Packet, PacketReadExt,
+    PacketWriteExt, framed::FrameCodec,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+use tokio_transponder::{bridge::Bridge, emulator::Calculator, serial::Codec};
+
+/// Long enough for a debug build of the server to slice up the demo image.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+fn init(user: &str) -> Packet {
+    Packet::Init {
+        capabilities: Capabilities::LOOK | Capabilities::RECONNECT,
+        options: ClientOptions {
+            color: Color::Cyan,
+            user: user.to_owned(),
+        },
+        // Small images keep the round quick, even over a serial link.
+        display: Display {
+            width: 32,
+            height: 16,
+            ..Display::default()
+        },
+    }
+}
+
+/// Starts a server on a free port with the demo image from `misc/`, and returns its address.
+async fn serve() -> SocketAddrV4 {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../misc");
+    let output = std::env::temp_dir().join(format!("geoterm-bridge-{}.geob", std::process::id()));
+    Bundle::build(&directory, &output).await.unwrap();
+    let bundle = Bundle::open(&output).await.unwrap();
+
+    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+        .await
+        .unwrap();
+    let SocketAddr::V4(address) = listener.local_addr().unwrap() else {
+        unreachable!();
+    };
+
+    let prefetch = Prefetch::new(Provider::Bundle(bundle), 1);
+    let mut server = Server::with_listener(listener, prefetch, Config::default());
+    tokio::spawn(async move { server.run().await });
+    address
+}
+
+/// Skips ahead to the next packet from the calculator's side that `matches`.
+async fn expect(calculator: &mut Calculator, matches: impl Fn(&Packet) -> bool) -> Packet {
+    loop {
+        let packet = timeout(TIMEOUT, calculator.recv()).await.unwrap().unwrap();
+        if matches(&packet) {
+            return packet;
+        }
+    }
+}
+
+#[tokio::test]
+async fn plays_a_round() {
+    let address = serve().await;
+
+    let codec = Codec::Framed(FrameCodec::default());
+    let mut calculator = Calculator::new(codec).unwrap();
+    let candidate = calculator.discovery().candidates().remove(0);
+    let bridge = Bridge {
+        address,
+        baud: 9600,
+        codec,
+    };
+    let _bridge = tokio::spawn(bridge.run(candidate));
+
+    calculator.send(init("calc")).await.unwrap();
+    expect(&mut calculator, |x| matches!(x, Packet::Confirmed { .. })).await;
+
+    // Someone else joins over TCP, since a round needs two players.
+    let (mut reader, mut writer) = TcpStream::connect(address).await.unwrap().buffered_split();
+    writer.write_packet(init("tcp")).await.unwrap();
+    writer.flush().await.unwrap();
+    loop {
+        let packet = timeout(TIMEOUT, reader.read_packet()).await.unwrap();
+        if matches!(packet.unwrap(), Packet::Confirmed { .. }) {
+            break;
+        }
+    }
+
+    calculator
+        .send(Packet::WaitingStatus { ready: true })
+        .await
+        .unwrap();
+    writer
+        .write_packet(Packet::WaitingStatus { ready: true })
+        .await
+        .unwrap();
+    writer.flush().await.unwrap();
+
+    let Packet::Round { number, .. } =
+        expect(&mut calculator, |x| matches!(x, Packet::Round { .. })).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(number, 0);
+}